@@ -0,0 +1,398 @@
+use std::collections::{HashMap, VecDeque};
+
+use pyo3::prelude::*;
+
+use super::util::RoadSection;
+
+// ports A/B Street's `Window` idea: rather than re-scanning the whole
+// episode's event log every time an RL training loop or dashboard asks "how
+// did throughput/latency look over the last N ticks", keep a ring buffer of
+// per-tick samples and let rolling_stats() fold however many of the most
+// recent ticks the caller asks for. updated incrementally over the course
+// of each Grid::tick() -- see the record_* methods below, called from the
+// same sites that already update GridStats -- rather than recomputed from
+// scratch.
+#[derive(Clone)]
+pub struct Analytics {
+    samples: VecDeque<TickSample>,
+    time_series: TimeSeries,
+}
+
+#[derive(Default, Clone)]
+struct TickSample {
+    passengers_picked_up: usize,
+    passengers_delivered: usize,
+    wait_ticks: Vec<usize>, // ticks_since_request of each passenger picked up this tick
+    trip_ticks: Vec<usize>, // ticks_since_pickup of each passenger dropped off this tick
+    out_of_battery: usize,
+    busy_cars: usize, // cars carrying >=1 passenger, as of this tick
+    total_cars: usize,
+    battery_consumed: f32, // total battery percent discharged across all cars this tick
+    charging_cars: usize,  // cars occupying a charging station slot, as of this tick
+    charging_capacity: usize, // summed capacity of every charging station, as of this tick
+    section_entries: HashMap<RoadSection, usize>, // cars entering each section this tick
+}
+
+impl Analytics {
+    // comfortably bigger than any rolling_stats() window a training loop or
+    // dashboard is likely to ask for, without keeping a whole long episode's
+    // samples (and every passenger's wait tick) in memory forever.
+    const MAX_WINDOW_TICKS: usize = 1000;
+
+    pub fn new(time_series_bucket_ticks: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::MAX_WINDOW_TICKS),
+            time_series: TimeSeries::new(time_series_bucket_ticks),
+        }
+    }
+
+    // must be called exactly once per Grid::tick(), before any of the
+    // record_* methods below -- pushes this tick's (initially empty) sample,
+    // evicting the oldest one once the window is full, and rolls the time
+    // series over into a fresh bucket once the current one's filled up.
+    pub fn begin_tick(&mut self) {
+        if self.samples.len() == Self::MAX_WINDOW_TICKS {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(TickSample::default());
+
+        self.time_series.begin_tick();
+    }
+
+    pub fn record_pickup(&mut self, wait_ticks: usize) {
+        let sample = self.current_tick();
+        sample.passengers_picked_up += 1;
+        sample.wait_ticks.push(wait_ticks);
+        self.time_series
+            .current_bucket()
+            .wait_ticks
+            .push(wait_ticks);
+    }
+
+    pub fn record_dropoff(&mut self, trip_ticks: usize) {
+        let sample = self.current_tick();
+        sample.passengers_delivered += 1;
+        sample.trip_ticks.push(trip_ticks);
+        self.time_series
+            .current_bucket()
+            .trip_ticks
+            .push(trip_ticks);
+    }
+
+    // called from tick_cars whenever a car's position.road_section changes,
+    // so the time series can report per-section (and, since TrafficLight is
+    // keyed the same way, per-intersection) throughput per window.
+    pub fn record_section_entry(&mut self, section: RoadSection) {
+        *self
+            .current_tick()
+            .section_entries
+            .entry(section)
+            .or_insert(0) += 1;
+        *self
+            .time_series
+            .current_bucket()
+            .section_entries
+            .entry(section)
+            .or_insert(0) += 1;
+    }
+
+    pub fn record_out_of_battery(&mut self) {
+        self.current_tick().out_of_battery += 1;
+    }
+
+    // call once per tick, once cars/passengers have settled for it, with a
+    // snapshot of how many cars are currently carrying at least one
+    // passenger out of how many cars exist in total.
+    pub fn record_car_utilization(&mut self, busy_cars: usize, total_cars: usize) {
+        let sample = self.current_tick();
+        sample.busy_cars = busy_cars;
+        sample.total_cars = total_cars;
+    }
+
+    // call once per car per tick it actually discharges (see
+    // Grid::tick_cars), with however much battery percent that car just
+    // burned -- folded into rolling_stats' battery_consumed below.
+    pub fn record_battery_consumed(&mut self, consumed: f32) {
+        self.current_tick().battery_consumed += consumed;
+    }
+
+    // call once per tick with how many cars are currently occupying a
+    // charging station slot (connecting/charging/disconnecting -- see
+    // ChargingStation.cars) out of how much capacity exists across every
+    // station, mirroring record_car_utilization's busy/total shape.
+    pub fn record_charging_utilization(&mut self, charging_cars: usize, charging_capacity: usize) {
+        let sample = self.current_tick();
+        sample.charging_cars = charging_cars;
+        sample.charging_capacity = charging_capacity;
+    }
+
+    fn current_tick(&mut self) -> &mut TickSample {
+        self.samples
+            .back_mut()
+            .expect("record_* called before begin_tick")
+    }
+
+    // the full episode's history of fixed-width windows, one entry per
+    // `time_series_bucket_ticks` ticks elapsed -- unlike rolling_stats's
+    // bounded, most-recent-N-ticks view, this is never evicted, so a
+    // training loop or dashboard can plot congestion hotspots and wait-time
+    // distributions over the whole run rather than only a recent snapshot
+    // or an end-of-run total.
+    pub fn time_series(&self) -> Vec<TimeSeriesBucket> {
+        self.time_series
+            .buckets
+            .iter()
+            .map(|bucket| TimeSeriesBucket {
+                section_entries: bucket
+                    .section_entries
+                    .iter()
+                    .map(|(&s, &n)| (s, n))
+                    .collect(),
+                wait_ticks: bucket.wait_ticks.clone(),
+                trip_ticks: bucket.trip_ticks.clone(),
+            })
+            .collect()
+    }
+
+    // folds the last `window_ticks` ticks (clamped to however much history
+    // is actually available) into a single snapshot. begin_tick's push/evict
+    // above is O(1) amortized per tick; this is O(k) in the window size, not
+    // O(1) -- an exact rolling p95 in O(1) needs an order-statistics
+    // structure (e.g. a t-digest) this repo has no dependency for, and isn't
+    // worth adding just for a query that's never on the per-tick hot path.
+    pub fn rolling_stats(&self, window_ticks: usize) -> RollingStats {
+        let window_ticks = window_ticks
+            .min(Self::MAX_WINDOW_TICKS)
+            .min(self.samples.len());
+        let window = self.samples.iter().rev().take(window_ticks);
+
+        let mut passengers_picked_up = 0;
+        let mut passengers_delivered = 0;
+        let mut wait_ticks = Vec::new();
+        let mut out_of_battery = 0;
+        let mut busy_car_ticks = 0usize;
+        let mut total_car_ticks = 0usize;
+        let mut battery_consumed = 0.0f32;
+        let mut charging_car_ticks = 0usize;
+        let mut charging_capacity_ticks = 0usize;
+
+        for sample in window {
+            passengers_picked_up += sample.passengers_picked_up;
+            passengers_delivered += sample.passengers_delivered;
+            wait_ticks.extend_from_slice(&sample.wait_ticks);
+            out_of_battery += sample.out_of_battery;
+            busy_car_ticks += sample.busy_cars;
+            total_car_ticks += sample.total_cars;
+            battery_consumed += sample.battery_consumed;
+            charging_car_ticks += sample.charging_cars;
+            charging_capacity_ticks += sample.charging_capacity;
+        }
+
+        let mean_wait_ticks = if wait_ticks.is_empty() {
+            0.0
+        } else {
+            wait_ticks.iter().sum::<usize>() as f32 / wait_ticks.len() as f32
+        };
+
+        wait_ticks.sort_unstable();
+        let p95_wait_ticks = match wait_ticks.len() {
+            0 => 0,
+            n => wait_ticks[(((n as f32) * 0.95) as usize).min(n - 1)],
+        };
+
+        let car_utilization = if total_car_ticks == 0 {
+            0.0
+        } else {
+            busy_car_ticks as f32 / total_car_ticks as f32
+        };
+
+        let out_of_battery_rate = if window_ticks == 0 {
+            0.0
+        } else {
+            out_of_battery as f32 / window_ticks as f32
+        };
+
+        let charging_station_utilization = if charging_capacity_ticks == 0 {
+            0.0
+        } else {
+            charging_car_ticks as f32 / charging_capacity_ticks as f32
+        };
+
+        RollingStats {
+            window_ticks,
+            passengers_picked_up,
+            passengers_delivered,
+            mean_wait_ticks,
+            p95_wait_ticks,
+            car_utilization,
+            out_of_battery_rate,
+            battery_consumed,
+            charging_station_utilization,
+        }
+    }
+
+    // the last `window_ticks` ticks (clamped the same way rolling_stats is),
+    // most recent first -- shared by the *_distribution/throughput_by_section
+    // queries below so they don't each re-derive the clamp.
+    fn window(&self, window_ticks: usize) -> impl Iterator<Item = &TickSample> {
+        let window_ticks = window_ticks
+            .min(Self::MAX_WINDOW_TICKS)
+            .min(self.samples.len());
+        self.samples.iter().rev().take(window_ticks)
+    }
+
+    // p50/p90/max wait time (tick spawned -> tick picked up) over the last
+    // `window_ticks` ticks.
+    pub fn wait_time_distribution(&self, window_ticks: usize) -> Distribution {
+        let wait_ticks = self
+            .window(window_ticks)
+            .flat_map(|sample| sample.wait_ticks.iter().copied())
+            .collect();
+        Distribution::of(wait_ticks)
+    }
+
+    // p50/p90/max trip time (tick picked up -> tick dropped off) over the
+    // last `window_ticks` ticks.
+    pub fn trip_time_distribution(&self, window_ticks: usize) -> Distribution {
+        let trip_ticks = self
+            .window(window_ticks)
+            .flat_map(|sample| sample.trip_ticks.iter().copied())
+            .collect();
+        Distribution::of(trip_ticks)
+    }
+
+    // how many cars entered each RoadSection (and, since TrafficLight is
+    // keyed the same way, each intersection) over the last `window_ticks`
+    // ticks -- a finer-grained, rolling counterpart to time_series's
+    // never-evicted per-bucket section_entries.
+    pub fn throughput_by_section(&self, window_ticks: usize) -> Vec<(RoadSection, usize)> {
+        let mut throughput = HashMap::new();
+        for sample in self.window(window_ticks) {
+            for (&section, &count) in &sample.section_entries {
+                *throughput.entry(section).or_insert(0) += count;
+            }
+        }
+        throughput.into_iter().collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[pyclass]
+pub struct RollingStats {
+    // ticks actually folded into this snapshot -- <= the window_ticks
+    // requested, since the window is clamped to however much history
+    // Analytics has actually kept
+    #[pyo3(get)]
+    pub window_ticks: usize,
+    #[pyo3(get)]
+    pub passengers_picked_up: usize,
+    #[pyo3(get)]
+    pub passengers_delivered: usize,
+    #[pyo3(get)]
+    pub mean_wait_ticks: f32,
+    #[pyo3(get)]
+    pub p95_wait_ticks: usize,
+    #[pyo3(get)]
+    pub car_utilization: f32,
+    #[pyo3(get)]
+    pub out_of_battery_rate: f32,
+    // total battery percent discharged across every (non-npc) car over the
+    // window, where a full battery is 1.0 -- e.g. 1.5 means the fleet
+    // collectively burned 1.5 full batteries' worth of charge.
+    #[pyo3(get)]
+    pub battery_consumed: f32,
+    // fraction of total charging-station capacity occupied, averaged over
+    // the window -- 1.0 means every slot, across every station, was occupied
+    // (connecting, charging, or disconnecting) every tick.
+    #[pyo3(get)]
+    pub charging_station_utilization: f32,
+}
+
+// p50/p90/max of a batch of tick-duration samples (wait or trip times) over
+// some window -- a coarser, three-number summary of the same samples
+// rolling_stats' mean/p95_wait_ticks already folds, for callers that want a
+// fuller shape of the distribution than a single percentile.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[pyclass]
+pub struct Distribution {
+    #[pyo3(get)]
+    pub p50: usize,
+    #[pyo3(get)]
+    pub p90: usize,
+    #[pyo3(get)]
+    pub max: usize,
+}
+
+impl Distribution {
+    fn of(mut values: Vec<usize>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        values.sort_unstable();
+        let n = values.len();
+        let percentile = |p: f32| values[(((n as f32) * p) as usize).min(n - 1)];
+
+        Self {
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            max: values[n - 1],
+        }
+    }
+}
+
+// ports A/B Street's TimeSeriesCount: the episode chopped into fixed-width
+// windows (`bucket_ticks` long) that, unlike Analytics's samples ring
+// buffer, are never evicted -- so time_series() can return the whole
+// episode's history for plotting, not just a recent rolling snapshot.
+#[derive(Clone)]
+struct TimeSeries {
+    bucket_ticks: usize,
+    ticks_into_bucket: usize,
+    buckets: Vec<Bucket>,
+}
+
+#[derive(Default, Clone)]
+struct Bucket {
+    section_entries: HashMap<RoadSection, usize>,
+    wait_ticks: Vec<usize>,
+    trip_ticks: Vec<usize>,
+}
+
+impl TimeSeries {
+    fn new(bucket_ticks: usize) -> Self {
+        assert!(bucket_ticks >= 1);
+        Self {
+            bucket_ticks,
+            ticks_into_bucket: 0,
+            buckets: vec![Bucket::default()],
+        }
+    }
+
+    fn begin_tick(&mut self) {
+        if self.ticks_into_bucket == self.bucket_ticks {
+            self.buckets.push(Bucket::default());
+            self.ticks_into_bucket = 0;
+        }
+        self.ticks_into_bucket += 1;
+    }
+
+    fn current_bucket(&mut self) -> &mut Bucket {
+        self.buckets.last_mut().expect("buckets is never empty")
+    }
+}
+
+// one `bucket_ticks`-wide window of Analytics::time_series() -- how many
+// cars entered each RoadSection (and, since TrafficLight is keyed the same
+// way, each intersection) that window, plus every passenger wait/trip
+// duration (in ticks) that completed during it.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[pyclass]
+pub struct TimeSeriesBucket {
+    #[pyo3(get)]
+    pub section_entries: Vec<(RoadSection, usize)>,
+    #[pyo3(get)]
+    pub wait_ticks: Vec<usize>,
+    #[pyo3(get)]
+    pub trip_ticks: Vec<usize>,
+}
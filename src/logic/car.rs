@@ -7,18 +7,21 @@ use std::{
 use macroquad::color::Color;
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{python::bridge::bridge::PyAction, render::car::CarRenderer};
+use crate::python::bridge::bridge::PyAction;
 
 use super::{
     car_agent::CarAgent,
+    car_model::CarModel,
     ev::{BatteryPercent, ChargingStation, ChargingStationId},
+    grid::Grid,
     passenger::{Passenger, PassengerId},
-    pathfinding::Path,
-    util::{Direction, RoadSection},
+    pathfinding::{Heuristic, Path},
+    util::{Direction, DrivingSide, RoadSection},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct CarId(usize);
 
@@ -30,7 +33,13 @@ impl CarId {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+// the front of a car, not the whole car: `position_in_section` is a single
+// point. a car of `length` cells actually spans
+// `position_in_section - length + 1 ..= position_in_section` (see
+// `occupied_cells`), so occupancy/collision checks (Grid::tick_cars,
+// Grid::gap_to_leader) look that span up via the CarId rather than treating
+// CarPosition itself as the occupied cell.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct CarPosition {
     #[pyo3(get)]
@@ -181,12 +190,54 @@ impl CarPosition {
         }
     }
 
+    // the cells a `length`-long car with its front here actually occupies,
+    // or None while parked at a charging station (those don't consume road
+    // capacity; see CarPosition::eq).
+    pub fn occupied_cells(&self, length: usize) -> Option<std::ops::RangeInclusive<usize>> {
+        if self.is_at_charging_station() {
+            return None;
+        }
+
+        let rear = self.position_in_section.saturating_sub(length.saturating_sub(1));
+        Some(rear..=self.position_in_section)
+    }
+
+    // whether a `length`-long car with its front at `self` would overlap a
+    // `other_length`-long car with its front at `other`, both measured
+    // within the same RoadSection
+    pub fn overlaps(&self, length: usize, other: &Self, other_length: usize) -> bool {
+        let Some(span) = self.occupied_cells(length) else {
+            return false;
+        };
+        let Some(other_span) = other.occupied_cells(other_length) else {
+            return false;
+        };
+
+        span.start() <= other_span.end() && other_span.start() <= span.end()
+    }
+
+    // same as `overlaps`, but for two cars already known to be on the same
+    // RoadSection and not at a charging station -- lets callers check
+    // against a raw position_in_section without building a CarPosition
+    pub fn positions_overlap(
+        position_in_section: usize,
+        length: usize,
+        other_position_in_section: usize,
+        other_length: usize,
+    ) -> bool {
+        let rear = position_in_section.saturating_sub(length.saturating_sub(1));
+        let other_rear =
+            other_position_in_section.saturating_sub(other_length.saturating_sub(1));
+
+        rear <= other_position_in_section && other_rear <= position_in_section
+    }
+
     pub fn is_at_intersection(&self) -> bool {
         self.position_in_section == self.road_section.direction.max_position_in_section()
     }
 
     pub fn path_to(self, other: CarPosition) -> Path {
-        Path::find(self, other, CarProps::SPEED)
+        Path::find(self, other, Heuristic::Manhattan)
     }
 
     pub fn distance_to(self, other: CarPosition) -> usize {
@@ -212,7 +263,7 @@ impl CarPosition {
         let charging_station_entrance = charging_station.entrance();
 
         let is_turning_left = decision == CarDecision::TurnLeft;
-        let drive_on_left_side = CarRenderer::ENGLAND_MODE;
+        let drive_on_left_side = DrivingSide::current().is_left();
 
         let new_position = match is_turning_left == drive_on_left_side {
             true => charging_station_entrance,
@@ -246,6 +297,12 @@ pub struct CarProps {
     pub colour: Color,
     pub speed: usize,        // ticks per movement
     pub discharge_rate: f32, // percent per tick
+    pub model: CarModel,
+
+    // how many CarPosition cells this car occupies, cached off model.cell_length
+    // at construction (à la OpenTTD's cached_veh_length) so occupancy checks
+    // don't have to dereference the model every time
+    pub length: usize,
 }
 
 impl CarProps {
@@ -256,6 +313,16 @@ impl CarProps {
         speed: usize,
         discharge_rate: f32,
         colour: Color,
+    ) -> Self {
+        Self::with_model(agent, speed, discharge_rate, colour, CarModel::default())
+    }
+
+    pub fn with_model(
+        agent: impl CarAgent + 'static,
+        speed: usize,
+        discharge_rate: f32,
+        colour: Color,
+        model: CarModel,
     ) -> Self {
         Self {
             id: CarId::next(),
@@ -263,25 +330,38 @@ impl CarProps {
             colour,
             speed,
             discharge_rate,
+            model,
+            length: model.cell_length,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CarPassenger {
-    PickingUp(PassengerId),
-    DroppingOff(Passenger),
+    // usize is this passenger's planned position in the car's drop-off
+    // sequence (see Route::cheapest_insertion's dropoff_idx), carried over
+    // to the DroppingOff variant once it's actually picked up so pooled
+    // rides alight in the planned order (see Grid::tick_passengers).
+    PickingUp(PassengerId, usize),
+    DroppingOff(Passenger, usize),
 }
 
 impl CarPassenger {
     pub fn is_dropping_off(&self) -> bool {
-        matches!(self, Self::DroppingOff(_))
+        matches!(self, Self::DroppingOff(..))
     }
 
     pub fn is_id(&self, id: PassengerId) -> bool {
         match self {
-            Self::PickingUp(passenger_id) => *passenger_id == id,
-            Self::DroppingOff(passenger) => passenger.id == id,
+            Self::PickingUp(passenger_id, _) => *passenger_id == id,
+            Self::DroppingOff(passenger, _) => passenger.id == id,
+        }
+    }
+
+    pub fn dropoff_order(&self) -> usize {
+        match self {
+            Self::PickingUp(_, order) => *order,
+            Self::DroppingOff(_, order) => *order,
         }
     }
 }
@@ -295,11 +375,24 @@ pub struct Car {
     pub passengers: Vec<CarPassenger>,
     pub battery: BatteryPercent,
     pub recent_actions: VecDeque<PyAction>,
+
+    // whether this car wanted to advance this tick but was held back because
+    // the car ahead of it (per Grid::section_queues) was within
+    // GridOpts::min_following_gap cells. purely informational: the renderer
+    // and Python bridge use it to show following/congestion state.
+    pub blocked_by_leader: bool,
 }
 
 impl Car {
     const RECENT_ACTIONS_LEN: usize = 5;
 
+    // safety margin on range estimates: needs_charge fires once the nearest
+    // free station is farther than range_cells() / CHARGE_RANGE_MARGIN away,
+    // not when it's exactly out of range, so congestion/heuristic slop
+    // between "should just make it" and "actually made it" doesn't strand
+    // the car short of the door.
+    const CHARGE_RANGE_MARGIN: f32 = 1.25;
+
     pub fn new(props: CarProps, position: CarPosition, battery: f32) -> Self {
         Self {
             ticks_until_next_movement: props.speed,
@@ -308,6 +401,7 @@ impl Car {
             passengers: vec![],
             battery: BatteryPercent::new(battery),
             recent_actions: VecDeque::with_capacity(Self::RECENT_ACTIONS_LEN),
+            blocked_by_leader: false,
         }
     }
 
@@ -316,7 +410,39 @@ impl Car {
     }
 
     pub fn find_path(&self, destination: CarPosition) -> Path {
-        Path::find(self.position, destination, self.props.speed)
+        Path::find(self.position, destination, Heuristic::Manhattan)
+    }
+
+    // how many cells this car can still travel before its battery hits
+    // empty, at its current discharge_rate and CarProps.speed pace
+    pub fn range_cells(&self) -> usize {
+        if self.props.discharge_rate <= 0.0 {
+            return usize::MAX; // never discharges: effectively unlimited range
+        }
+
+        let ticks_left = self.battery.get() / self.props.discharge_rate;
+        (ticks_left / self.props.speed as f32).max(0.0) as usize
+    }
+
+    // true once the nearest free charging station is projected to be out of
+    // reach before the battery runs dry, i.e. this car should head there now
+    // rather than keep driving its current errand and risk stranding itself.
+    pub fn needs_charge(&self, grid: &Grid) -> bool {
+        if self.position.is_at_charging_station() {
+            return false;
+        }
+
+        let Some(nearest_distance) = grid
+            .charging_stations
+            .values()
+            .filter(|cs| cs.has_space())
+            .map(|cs| self.position.distance_to(cs.entrance))
+            .min()
+        else {
+            return false; // no free station to head to anyway
+        };
+
+        (nearest_distance as f32 * Self::CHARGE_RANGE_MARGIN) >= self.range_cells() as f32
     }
 
     pub fn next_position(
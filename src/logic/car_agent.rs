@@ -5,8 +5,13 @@ use crate::{
         py_grid::PyGridState,
     },
 };
+use pyo3::prelude::*;
 use rand::seq::SliceRandom;
-use std::{io::Write, sync::Mutex};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
 
 use super::{
     car::{CarDecision, CarId, CarPassenger, CarPosition},
@@ -14,8 +19,9 @@ use super::{
     grid::Grid,
     grid_util::GridStats,
     passenger::{Passenger, PassengerId},
-    pathfinding::Path,
-    util::Direction,
+    pathfinding::{Heuristic, MultiLegPath, Path},
+    router::{ActionAtEnd, Goal, Router},
+    util::{Direction, HashMap, HashSet},
 };
 
 // pub trait CarAgent: Send + Sync {
@@ -25,12 +31,26 @@ pub trait CarAgent: Send + std::fmt::Debug {
         None
     }
 
+    // mutable counterpart of as_path_agent, used by Grid::close_section to
+    // force a car whose cached path now crosses a newly-closed section to
+    // recompute (see CarPathAgent::invalidate_path).
+    fn as_path_agent_mut(&mut self) -> Option<&mut dyn CarPathAgent> {
+        None
+    }
+
     fn as_py_agent(&self) -> Option<&PythonAgent> {
         None
     }
     fn is_npc(&self) -> bool {
         self.as_py_agent().is_none()
     }
+
+    // mutable downcast counterpart to as_py_agent, used by
+    // Grid::set_gym_action to reach the pending action slot of a
+    // GymAgent-driven car.
+    fn as_gym_agent_mut(&mut self) -> Option<&mut GymAgent> {
+        None
+    }
 }
 pub trait CarPathAgent: CarAgent {
     // pick a destination, generate a path, and store it.
@@ -46,6 +66,23 @@ pub trait CarPathAgent: CarAgent {
     fn as_py_agent(&self) -> Option<&PythonAgent> {
         None
     }
+
+    fn as_gym_agent_mut(&mut self) -> Option<&mut GymAgent> {
+        None
+    }
+
+    // where a shuttle-driving agent currently is along its route, for
+    // agents that have one (see FixedRoute). None for every other agent.
+    fn shuttle_status(&self) -> Option<ShuttleStatus> {
+        None
+    }
+
+    // drops any cached path/plan, so the next calculate_path call replans
+    // from scratch instead of continuing to drive a route that may now cross
+    // a closed section -- see Grid::close_section. the default is a no-op, so
+    // an agent that doesn't override it (FixedRoute, PythonAgent, GymAgent)
+    // keeps whatever path it already holds.
+    fn invalidate_path(&mut self) {}
 }
 
 impl<T: CarPathAgent> CarAgent for T {
@@ -113,9 +150,17 @@ impl<T: CarPathAgent> CarAgent for T {
         Some(self)
     }
 
+    fn as_path_agent_mut(&mut self) -> Option<&mut dyn CarPathAgent> {
+        Some(self)
+    }
+
     fn as_py_agent(&self) -> Option<&PythonAgent> {
         CarPathAgent::as_py_agent(self)
     }
+
+    fn as_gym_agent_mut(&mut self) -> Option<&mut GymAgent> {
+        CarPathAgent::as_gym_agent_mut(self)
+    }
 }
 
 // temporary placeholder agent to put instead of the real agent
@@ -134,43 +179,98 @@ pub struct RandomTurns {}
 impl CarAgent for RandomTurns {
     fn get_turn(&mut self, grid: &mut Grid, car_id: CarId) -> CarDecision {
         let car_position = grid.car_position(car_id);
-        let options = car_position.possible_decisions();
+        let options = grid.possible_decisions_avoiding_full_sections(car_position);
         *options
-            .choose(&mut rand::thread_rng())
+            .choose(grid.car_rng_mut())
             .expect("List of possible car decisions is empty")
     }
 }
 
 #[derive(Default, Debug)]
 pub struct RandomDestination {
-    path: Option<Path>,
+    router: Option<Router>,
 }
 
-impl CarPathAgent for RandomDestination {
-    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
-        loop {
-            let car = grid.car(car_id);
-            let destination = CarPosition::random(&mut rand::thread_rng());
+impl RandomDestination {
+    // keep driving towards the current random destination until it's
+    // reached, instead of replacing it with a new one every tick: that
+    // used to mean a fresh Path::find_weighted call (and a fresh random
+    // destination) on every single get_turn, even mid-journey.
+    fn pick_new_destination(grid: &mut Grid, car_id: CarId) -> Path {
+        let congestion = grid
+            .opts
+            .route_around_congestion
+            .then(|| grid.section_congestion());
 
-            let mut path = car.find_path(destination);
+        loop {
+            let car_position = grid.car(car_id).position;
+            let destination = CarPosition::random(grid.car_rng_mut());
+
+            let mut path = Path::find_weighted(
+                car_position,
+                destination,
+                Heuristic::Manhattan,
+                grid.opts.npc_heuristic_weight,
+                congestion,
+                grid.closed_sections(),
+                grid.opts.npc_beam_width,
+            );
             if path.next_decision().is_none() {
                 continue;
             }
 
             path.action = Some(AgentAction::HeadTowards(Direction::Up));
-            self.path = Some(path);
-            break;
+            return path;
+        }
+    }
+}
+
+impl CarPathAgent for RandomDestination {
+    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        let car_position = grid.car(car_id).position;
+
+        let router = self
+            .router
+            .get_or_insert_with(|| Router::new(car_id, Goal::RoamRandomly));
+
+        if !router.reached_goal(car_position) {
+            // still on the way to the current random destination: pop the
+            // sections already driven off the cached path, so its
+            // next_decision starts from the car's current section
+            let driven = router.path().and_then(|path| {
+                path.sections
+                    .iter()
+                    .position(|section| *section == car_position.road_section)
+            });
+            if let Some(driven) = driven.filter(|&driven| driven > 0) {
+                let mut path = router.path().unwrap().clone();
+                path.sections.drain(..driven);
+                path.turns.drain(..driven);
+                router.set_goal(Goal::RoamRandomly, path);
+            }
+            return;
         }
+
+        // ActionAtEnd::GiveUpAndReroute is the only outcome RoamRandomly
+        // ever produces, so there's nothing to match on: just pick a new
+        // destination and keep going
+        debug_assert_eq!(router.action_at_end(), ActionAtEnd::GiveUpAndReroute);
+        let path = Self::pick_new_destination(grid, car_id);
+        router.set_goal(Goal::RoamRandomly, path);
     }
 
     fn get_path(&self) -> Option<&Path> {
-        self.path.as_ref()
+        self.router.as_ref().and_then(Router::path)
+    }
+
+    fn invalidate_path(&mut self) {
+        self.router = None;
     }
 }
 
 #[derive(Default, Debug)]
 pub struct NearestPassenger {
-    path: Option<Path>,
+    plan: Option<MultiLegPath>,
 }
 
 impl NearestPassenger {
@@ -188,71 +288,729 @@ impl NearestPassenger {
             .unwrap();
         Some(closest_passenger)
     }
+
+    // turns a car's onboard passengers (a mix of still-to-be-picked-up and
+    // already-riding ones) into the waypoints a MultiLegPath should visit
+    fn waypoints_for(grid: &Grid, car: &super::car::Car) -> Option<Vec<(CarPosition, AgentAction)>> {
+        if car.passengers.is_empty() {
+            return None;
+        }
+
+        Some(
+            car.passengers
+                .iter()
+                .map(|p| match p {
+                    CarPassenger::PickingUp(passenger_id, _) => {
+                        let passenger = grid.get_idle_passenger(*passenger_id).unwrap();
+                        (passenger.start, AgentAction::PickUp(passenger.id))
+                    }
+                    CarPassenger::DroppingOff(passenger, _) => {
+                        (passenger.destination, AgentAction::DropOff(passenger.id))
+                    }
+                })
+                .collect(),
+        )
+    }
 }
 
 impl CarPathAgent for NearestPassenger {
     fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        let heuristic_weight = grid.opts.npc_heuristic_weight;
+        let beam_width = grid.opts.npc_beam_width;
+
         let car = grid.car(car_id);
         if let Some(cs_id) = car.position.in_charging_station {
             if car.battery.get() < 1.0 {
                 let cs = grid.charging_stations.get(&cs_id).unwrap();
-                let mut path = car.position.path_to(cs.entrance);
-                path.action = Some(AgentAction::ChargeBattery(cs_id));
-                self.path = Some(path);
+                let entrance = cs.entrance;
+                let congestion = grid
+                    .opts
+                    .route_around_congestion
+                    .then(|| grid.section_congestion());
+                self.plan = Some(MultiLegPath::plan(
+                    car.position,
+                    vec![(entrance, AgentAction::ChargeBattery(cs_id))],
+                    |_, _| false,
+                    Heuristic::Manhattan,
+                    heuristic_weight,
+                    congestion,
+                    grid.closed_sections(),
+                    beam_width,
+                ));
                 return;
             }
-        } else if car.battery.get() < 0.1 {
-            let cs_ids_and_paths = grid
+        } else if car.needs_charge(grid) {
+            let cs_id_and_entrance = grid
                 .charging_stations
                 .values()
                 .filter(|cs| cs.has_space())
-                .map(|cs| (cs.id, car.position.path_to(cs.entrance)));
-            let cs_id_and_path = cs_ids_and_paths.min_by_key(|(_, p)| p.cost);
-            if let Some((cs_id, mut path)) = cs_id_and_path {
-                path.action = Some(AgentAction::ChargeBattery(cs_id));
-                self.path = Some(path);
+                .map(|cs| (cs.id, cs.entrance))
+                .min_by_key(|(_, entrance)| car.position.distance_to(*entrance));
+            if let Some((cs_id, entrance)) = cs_id_and_entrance {
+                let congestion = grid
+                    .opts
+                    .route_around_congestion
+                    .then(|| grid.section_congestion());
+                self.plan = Some(MultiLegPath::plan(
+                    car.position,
+                    vec![(entrance, AgentAction::ChargeBattery(cs_id))],
+                    |_, _| false,
+                    Heuristic::Manhattan,
+                    heuristic_weight,
+                    congestion,
+                    grid.closed_sections(),
+                    beam_width,
+                ));
                 return;
             }
         }
 
-        if car.passengers.is_empty() {
-            // assign ourselves to the closest passenger
-            let closest_passenger = self.pick_passenger(grid, car_id);
-            let Some(closest_passenger) = closest_passenger else {
-                // no available passengers, just roam randomly
-                let mut random_agent = RandomDestination::default();
-                random_agent.calculate_path(grid, car_id);
-                self.path = random_agent.get_path().cloned();
-                return;
+        // top up with newly assigned passengers while there's spare seats
+        while grid.car(car_id).passengers.len() < grid.opts.passengers_per_car {
+            let Some(closest_passenger) = self.pick_passenger(grid, car_id) else {
+                break;
             };
-
             grid.assign_car_to_passenger(car_id, closest_passenger.id);
         }
 
         let car = grid.car(car_id);
-        let first_passenger = &car.passengers[0];
+        let car_position = car.position;
+        let Some(waypoints) = Self::waypoints_for(grid, car) else {
+            // no passengers onboard or assigned, just roam randomly
+            let mut random_agent = RandomDestination::default();
+            random_agent.calculate_path(grid, car_id);
+            let path = random_agent.get_path().cloned();
+            let congestion = grid
+                .opts
+                .route_around_congestion
+                .then(|| grid.section_congestion());
+            self.plan = path.map(|path| {
+                let action = path.action.unwrap();
+                let range = grid.car(car_id).range_cells();
+                let charging_stations = grid
+                    .charging_stations
+                    .values()
+                    .filter(|cs| cs.has_space())
+                    .map(|cs| (cs.id, cs.entrance));
+                MultiLegPath::plan_chargeable(
+                    car_position,
+                    path.destination,
+                    action,
+                    range,
+                    charging_stations,
+                    Heuristic::Manhattan,
+                    heuristic_weight,
+                    congestion,
+                    grid.closed_sections(),
+                    beam_width,
+                )
+            });
+            return;
+        };
+
+        let actions: Vec<AgentAction> = waypoints.iter().map(|(_, action)| *action).collect();
+        let precedes = move |a: usize, b: usize| {
+            matches!(
+                (actions[a], actions[b]),
+                (AgentAction::PickUp(pick), AgentAction::DropOff(drop)) if pick == drop
+            )
+        };
+
+        let congestion = grid
+            .opts
+            .route_around_congestion
+            .then(|| grid.section_congestion());
+        self.plan = Some(MultiLegPath::plan(
+            car_position,
+            waypoints,
+            precedes,
+            Heuristic::Manhattan,
+            heuristic_weight,
+            congestion,
+            grid.closed_sections(),
+            beam_width,
+        ));
+    }
+
+    fn get_path(&self) -> Option<&Path> {
+        self.plan.as_ref().map(MultiLegPath::current_leg)
+    }
+
+    fn invalidate_path(&mut self) {
+        self.plan = None;
+    }
+}
+
+// a fixed-route's stops, à la A/B Street's Route: an ordered list of
+// CarPosition stops the shuttle cycles through forever. `loop_back` means
+// the last stop links straight back to the first (a one-directional
+// loop); otherwise the shuttle bounces back and forth, reversing
+// direction at either end (a there-and-back line). configured from Python
+// via GridOpts::shuttle_routes and seeded one FixedRoute car per route in
+// Grid::new, the same way agent/npc cars are seeded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[pyclass]
+pub struct ShuttleRoute {
+    #[pyo3(get)]
+    pub stops: Vec<CarPosition>,
+    #[pyo3(get)]
+    pub loop_back: bool,
+    #[pyo3(get)]
+    pub dwell_ticks: usize,
+}
+
+#[pymethods]
+impl ShuttleRoute {
+    #[new]
+    fn new(stops: Vec<CarPosition>, loop_back: bool, dwell_ticks: usize) -> Self {
+        assert!(stops.len() >= 2, "a fixed route needs at least two stops");
+        Self {
+            stops,
+            loop_back,
+            dwell_ticks,
+        }
+    }
+}
+
+impl ShuttleRoute {
+    // the stop after `current` (and the direction the shuttle will be
+    // travelling once it gets there), given it's currently heading `forward`.
+    fn next_stop(&self, current: usize, forward: bool) -> (usize, bool) {
+        let last = self.stops.len() - 1;
+
+        if self.loop_back {
+            return ((current + 1) % self.stops.len(), true);
+        }
+
+        match (forward, current) {
+            (true, c) if c < last => (c + 1, true),
+            (false, c) if c > 0 => (c - 1, false),
+            // hit an end: turn around
+            (true, _) => (current - 1, false),
+            (false, _) => (current + 1, true),
+        }
+    }
+
+    // every (from, to) pair of stop indices the shuttle will ever drive
+    // directly between, so their paths can be precomputed once
+    fn leg_pairs(&self) -> Vec<(usize, usize)> {
+        if self.loop_back {
+            return (0..self.stops.len())
+                .map(|i| (i, (i + 1) % self.stops.len()))
+                .collect();
+        }
+
+        (0..self.stops.len().saturating_sub(1))
+            .flat_map(|i| [(i, i + 1), (i + 1, i)])
+            .collect()
+    }
+}
+
+// whatever a shuttle-driving CarPathAgent currently knows about its route,
+// exposed read-only so it can be surfaced to the Python side (e.g. for a
+// multi-agent dispatcher that wants to know where the fleet's shuttles are).
+#[derive(Clone, Copy, Debug)]
+pub struct ShuttleStatus {
+    pub stop_index: usize,
+    pub at_stop: bool,
+}
+
+// a scheduled-transit baseline, à la A/B Street's TransitSimState: cycles
+// through a ShuttleRoute forever, dwelling `dwell_ticks` at each stop to
+// board and alight passengers, rather than reactively chasing the nearest
+// one. a stop's dwell is implemented by overriding the car's own movement
+// cooldown (Car::ticks_until_next_movement), so the usual per-tick
+// CarDecision is simply ignored for as long as the shuttle is parked.
+#[derive(Debug)]
+pub struct FixedRoute {
+    route: ShuttleRoute,
+    legs: HashMap<(usize, usize), Path>, // precomputed once, keyed by stop index pair
+    dwell_ticks: usize,
+    state: ShuttleState,
+    forward: bool,
+    joined_route: bool,
+    path: Option<Path>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ShuttleState {
+    DrivingToStop(usize),
+    AtStop(usize),
+}
+
+impl FixedRoute {
+    pub fn new(route: ShuttleRoute) -> Self {
+        let legs = route
+            .leg_pairs()
+            .into_iter()
+            .map(|(from, to)| {
+                let path = Path::find(route.stops[from], route.stops[to], Heuristic::Manhattan);
+                ((from, to), path)
+            })
+            .collect();
+
+        let dwell_ticks = route.dwell_ticks;
+
+        Self {
+            route,
+            legs,
+            dwell_ticks,
+            state: ShuttleState::AtStop(0),
+            forward: true,
+            joined_route: false,
+            path: None,
+        }
+    }
+
+    // every stop the shuttle will call at starting at (and including) `stop`,
+    // looking `hops` legs ahead -- enough to cover a full loop (or round
+    // trip, for a there-and-back route), so a waiting passenger's
+    // destination can be checked against "anywhere still ahead of us", not
+    // just the next stop.
+    fn stops_ahead(&self, mut stop: usize, mut forward: bool, hops: usize) -> Vec<CarPosition> {
+        let mut stops = vec![self.route.stops[stop]];
+        for _ in 0..hops {
+            let (next, next_forward) = self.route.next_stop(stop, forward);
+            stops.push(self.route.stops[next]);
+            stop = next;
+            forward = next_forward;
+        }
+        stops
+    }
+
+    // board every waiting passenger (up to the car's capacity) who's within
+    // opts.passenger_radius of the stop we're currently dwelling at (close
+    // enough to have walked over) and whose dropoff is within the same
+    // radius of this stop or any stop further along the route -- so the
+    // normal drop-off-on-arrival mechanism (Grid::tick_passengers) handles
+    // alighting without the shuttle ever detouring off its route.
+    //
+    // this only opportunistically matches passengers against the route
+    // *this* FixedRoute is already dwelling on; it deliberately doesn't
+    // reach into Grid::tick_passengers to reassign an arbitrary idle
+    // passenger onto a shuttle elsewhere on the grid, which would mean
+    // either teleporting them to the stop or having the shuttle detour to
+    // fetch them -- both break the "never detours" invariant above.
+    fn board_waiting_passengers(&self, grid: &mut Grid, car_id: CarId, stop: usize, forward: bool) {
+        let hops = self.route.stops.len().max(1);
+        let ahead_stops = self.stops_ahead(stop, forward, hops);
+        let board_stop = self.route.stops[stop];
+        let radius = grid.opts.passenger_radius;
+
+        while grid.car(car_id).passengers.len() < grid.opts.passengers_per_car {
+            let boardable = grid
+                .unassigned_passengers()
+                .into_iter()
+                .find(|p| {
+                    p.start.distance_to(board_stop) <= radius
+                        && ahead_stops
+                            .iter()
+                            .any(|&stop| p.destination.distance_to(stop) <= radius)
+                })
+                .map(|p| p.id);
+
+            let Some(passenger_id) = boardable else {
+                break;
+            };
+            grid.assign_car_to_passenger(car_id, passenger_id);
+        }
+    }
+}
+
+impl CarPathAgent for FixedRoute {
+    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        let car_position = grid.car(car_id).position;
+
+        if !self.joined_route {
+            if car_position == self.route.stops[0] {
+                self.joined_route = true;
+                self.state = ShuttleState::AtStop(0);
+                grid.car_mut(car_id).ticks_until_next_movement = self.dwell_ticks;
+            } else {
+                // first tick: drive from wherever we spawned to the route's
+                // first stop, then start cycling through it
+                let car = grid.car(car_id);
+                let mut path = car.find_path(self.route.stops[0]);
+                path.action = Some(AgentAction::HeadTowards(Direction::Up));
+                self.path = Some(path);
+                return;
+            }
+        } else if let ShuttleState::DrivingToStop(stop) = self.state {
+            if car_position == self.route.stops[stop] {
+                self.state = ShuttleState::AtStop(stop);
+                grid.car_mut(car_id).ticks_until_next_movement = self.dwell_ticks;
+            }
+        }
+
+        match self.state {
+            ShuttleState::AtStop(stop) => {
+                let (next_stop, forward) = self.route.next_stop(stop, self.forward);
+                self.board_waiting_passengers(grid, car_id, stop, forward);
+
+                if grid.car(car_id).ticks_until_next_movement > 0 {
+                    // still dwelling: whatever CarDecision we hand out this
+                    // tick is moot, the movement cooldown overrides it
+                    self.path = None;
+                } else {
+                    self.forward = forward;
+                    self.state = ShuttleState::DrivingToStop(next_stop);
+                    self.path = Some(self.legs[&(stop, next_stop)].clone());
+                }
+            }
+            ShuttleState::DrivingToStop(_) => {
+                // mid-leg: self.path already holds the precomputed leg
+            }
+        }
+    }
+
+    fn get_path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+
+    fn shuttle_status(&self) -> Option<ShuttleStatus> {
+        let (stop_index, at_stop) = match self.state {
+            ShuttleState::AtStop(stop) => (stop, true),
+            ShuttleState::DrivingToStop(stop) => (stop, false),
+        };
+        Some(ShuttleStatus { stop_index, at_stop })
+    }
+}
+
+// the marginal cost of inserting one passenger's pickup/dropoff into a
+// car's route, as returned by Grid::assign_car_to_passenger_pooled so an
+// external dispatcher comparing several candidate cars for the same
+// passenger can pick whichever quotes the smallest added_cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detour {
+    pub pickup_idx: usize,
+    pub dropoff_idx: usize,
+    pub added_cost: i64,
+}
+
+// a car's planned stops for Dispatcher::plan's cheapest-insertion search
+// below (and Grid::assign_car_to_passenger_pooled's single-car version of
+// the same heuristic): its current position followed by every CarPosition
+// it still needs to visit, paired with the onboard-count delta at each stop
+// (+1 picking a passenger up, -1 dropping one off) so a candidate insertion
+// can be checked against opts.passengers_per_car before it's committed.
+pub(crate) struct Route {
+    start: CarPosition,
+    onboard: i32, // passengers already aboard, before any stop in `stops`
+    stops: Vec<(CarPosition, i32)>,
+}
+
+impl Route {
+    pub(crate) fn from_car(car: &Car) -> Self {
+        // car.passengers only ever holds CarPassenger::DroppingOff at this
+        // point: PickingUp entries are wiped at the start of every car's
+        // own turn (see Grid::car_remove_pick_up_commands), and Dispatcher
+        // hasn't written any new ones for this tick yet.
+        let stops = car
+            .passengers
+            .iter()
+            .map(|p| match p {
+                CarPassenger::DroppingOff(p, _) => (p.destination, -1),
+                CarPassenger::PickingUp(..) => unreachable!(
+                    "car.passengers should only hold DroppingOff before Dispatcher::plan runs"
+                ),
+            })
+            .collect::<Vec<_>>();
+        let onboard = stops.len() as i32;
+        Self {
+            start: car.position,
+            onboard,
+            stops,
+        }
+    }
+
+    fn positions(&self) -> Vec<CarPosition> {
+        std::iter::once(self.start)
+            .chain(self.stops.iter().map(|(p, _)| *p))
+            .collect()
+    }
+
+    fn route_cost(positions: &[CarPosition]) -> i64 {
+        positions
+            .windows(2)
+            .map(|w| w[0].distance_to(w[1]) as i64)
+            .sum()
+    }
+
+    // onboard count just before stops[i] is visited (prefix[0] is the
+    // count right now, before `start`)
+    fn onboard_prefix(&self) -> Vec<i32> {
+        let mut prefix = Vec::with_capacity(self.stops.len() + 1);
+        prefix.push(self.onboard);
+        for &(_, delta) in &self.stops {
+            prefix.push(prefix.last().unwrap() + delta);
+        }
+        prefix
+    }
+
+    // cheapest (pickup_idx, dropoff_idx, added_cost) to insert `pickup`
+    // right before stops[pickup_idx] and `dropoff` right before
+    // stops[dropoff_idx] (both in 0..=stops.len(), dropoff_idx >=
+    // pickup_idx), or None if every placement would push the car's
+    // onboard count above max_onboard at some point between the two.
+    pub(crate) fn cheapest_insertion(
+        &self,
+        pickup: CarPosition,
+        dropoff: CarPosition,
+        max_onboard: usize,
+    ) -> Option<(usize, usize, i64)> {
+        let positions = self.positions();
+        let base_cost = Self::route_cost(&positions);
+        let prefix = self.onboard_prefix();
+
+        let mut best: Option<(usize, usize, i64)> = None;
+        for pickup_idx in 0..=self.stops.len() {
+            for dropoff_idx in pickup_idx..=self.stops.len() {
+                // a conservative (not necessarily tightest) upper bound on
+                // the onboard count while the new passenger would be
+                // riding: good enough for a baseline, never lets a car
+                // over capacity.
+                let peak_onboard = prefix[pickup_idx..=dropoff_idx]
+                    .iter()
+                    .copied()
+                    .max()
+                    .unwrap();
+                if peak_onboard + 1 > max_onboard as i32 {
+                    continue;
+                }
 
-        let path = match &first_passenger {
-            CarPassenger::PickingUp(passenger_id) => {
-                let passenger = grid.get_idle_passenger(*passenger_id).unwrap();
-                let mut path = car.find_path(passenger.start);
+                let mut with_insertion = positions.clone();
+                with_insertion.insert(dropoff_idx + 1, dropoff);
+                with_insertion.insert(pickup_idx + 1, pickup);
+                let added = Self::route_cost(&with_insertion) - base_cost;
 
-                path.action = Some(AgentAction::PickUp(*passenger_id));
-                path
+                if best.map_or(true, |(_, _, b)| added < b) {
+                    best = Some((pickup_idx, dropoff_idx, added));
+                }
             }
+        }
+        best
+    }
+
+    fn insert(
+        &mut self,
+        pickup_idx: usize,
+        dropoff_idx: usize,
+        pickup: CarPosition,
+        dropoff: CarPosition,
+    ) {
+        self.stops.insert(dropoff_idx, (dropoff, -1));
+        self.stops.insert(pickup_idx, (pickup, 1));
+    }
+}
+
+#[cfg(test)]
+fn test_route_position(position_in_section: usize) -> CarPosition {
+    CarPosition {
+        road_section: RoadSection::get(Direction::Right, 0, 0),
+        position_in_section,
+        in_charging_station: None,
+    }
+}
+
+#[test]
+fn test_route_cheapest_insertion_onboard_capacity_bound() {
+    // a route with one already-committed future stop (a pickup for some
+    // other passenger, +1 onboard) further along than the new trip's
+    // pickup/dropoff: slotting the new trip in before that stop keeps the
+    // car at 1 onboard and fits under max_onboard, but slotting it in
+    // after would momentarily put 2 passengers onboard, over the bound.
+    let route = Route {
+        start: test_route_position(0),
+        onboard: 0,
+        stops: vec![(test_route_position(10), 1)],
+    };
+    let pickup = test_route_position(3);
+    let dropoff = test_route_position(7);
+
+    let (pickup_idx, dropoff_idx, added_cost) =
+        route.cheapest_insertion(pickup, dropoff, 1).unwrap();
+    assert_eq!((pickup_idx, dropoff_idx), (0, 0));
+    // pickup/dropoff both fall exactly on the existing straight-line path,
+    // so inserting them adds no extra distance
+    assert_eq!(added_cost, 0);
+
+    // dropping max_onboard to 0 rules out every slot, since even the
+    // cheapest one would put one passenger onboard
+    assert_eq!(route.cheapest_insertion(pickup, dropoff, 0), None);
+}
+
+// shared state all of one fleet's CentralDispatch-driven cars read from and
+// write into -- construct one Arc<Mutex<Dispatcher>> per fleet and clone it
+// into each car's CentralDispatch::new. a Mutex rather than an
+// Rc<RefCell<_>> since CarAgent requires Send (see PythonAgent's
+// half_transitions for the same reasoning).
+#[derive(Default, Debug)]
+pub struct Dispatcher {
+    fleet: HashSet<CarId>,
+    planned_tick: Option<usize>,
+    assignments: HashMap<CarId, Vec<PassengerId>>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-            CarPassenger::DroppingOff(passenger) => {
-                let mut path = car.find_path(passenger.destination);
-                path.action = Some(AgentAction::DropOff(passenger.id));
-                path
+    // a cheapest-insertion VRP heuristic: repeatedly find the single
+    // (car, passenger, pickup_idx, dropoff_idx) insertion that adds the
+    // least Manhattan distance across the whole fleet, commit it, and
+    // keep going until no unassigned passenger can be feasibly inserted
+    // anywhere. recomputed from scratch every tick (rather than kept as a
+    // standing multi-tick plan) since passengers and cars come and go
+    // every tick and car.passengers' PickingUp entries don't survive past
+    // it anyway -- see Route::from_car.
+    //
+    // this is O(passengers * cars * route_len^3) per insertion, which is
+    // fine for a non-learning baseline to benchmark the Python RL agents
+    // against but isn't meant to scale to a huge fleet or deep routes.
+    //
+    // battery/charging isn't folded into the search -- cars that need to
+    // charge or are already at a charging station are simply excluded
+    // from this tick's assignment, same as the request asks; they're
+    // left to whatever the rest of their agent does (nothing, here: a
+    // full VRP dispatcher would need to plan charging stops alongside
+    // passenger stops, which is future work).
+    fn plan(&mut self, grid: &Grid) {
+        self.assignments.clear();
+
+        let max_onboard = grid.opts.passengers_per_car;
+        let mut routes: HashMap<CarId, Route> = self
+            .fleet
+            .iter()
+            .filter(|&&car_id| {
+                let car = grid.car(car_id);
+                !car.needs_charge(grid) && car.position.in_charging_station.is_none()
+            })
+            .map(|&car_id| (car_id, Route::from_car(grid.car(car_id))))
+            .collect();
+
+        let mut unassigned: Vec<&Passenger> = grid.unassigned_passengers();
+
+        loop {
+            let mut best: Option<(CarId, usize, usize, usize, i64)> = None;
+
+            for (&car_id, route) in &routes {
+                for (i, passenger) in unassigned.iter().enumerate() {
+                    let Some((pickup_idx, dropoff_idx, added)) = route.cheapest_insertion(
+                        passenger.start,
+                        passenger.destination,
+                        max_onboard,
+                    ) else {
+                        continue;
+                    };
+                    if best.map_or(true, |(_, _, _, _, b)| added < b) {
+                        best = Some((car_id, i, pickup_idx, dropoff_idx, added));
+                    }
+                }
             }
+
+            let Some((car_id, passenger_idx, pickup_idx, dropoff_idx, _)) = best else {
+                break;
+            };
+
+            let passenger = unassigned.remove(passenger_idx);
+            routes.get_mut(&car_id).unwrap().insert(
+                pickup_idx,
+                dropoff_idx,
+                passenger.start,
+                passenger.destination,
+            );
+            self.assignments
+                .entry(car_id)
+                .or_default()
+                .push(passenger.id);
+        }
+
+        self.planned_tick = Some(grid.ticks_passed);
+    }
+}
+
+// a single fleet controller instead of every car deciding independently:
+// all of a fleet's cars share one Dispatcher, whose cheapest-insertion
+// plan (computed once per tick, lazily, by whichever dispatched car's
+// turn comes first) jointly assigns waiting passengers to cars. each car
+// then just drains its own share of that plan and drives it, the same way
+// NearestPassenger drives whatever passengers are in car.passengers.
+#[derive(Debug)]
+pub struct CentralDispatch {
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    plan: Option<MultiLegPath>,
+}
+
+impl CentralDispatch {
+    pub fn new(dispatcher: Arc<Mutex<Dispatcher>>) -> Self {
+        Self {
+            dispatcher,
+            plan: None,
+        }
+    }
+}
+
+impl CarPathAgent for CentralDispatch {
+    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        {
+            let mut dispatcher = self.dispatcher.lock().unwrap();
+            dispatcher.fleet.insert(car_id);
+            if dispatcher.planned_tick != Some(grid.ticks_passed) {
+                dispatcher.plan(grid);
+            }
+
+            if let Some(passenger_ids) = dispatcher.assignments.remove(&car_id) {
+                for passenger_id in passenger_ids {
+                    grid.assign_car_to_passenger(car_id, passenger_id);
+                }
+            }
+        }
+
+        let heuristic_weight = grid.opts.npc_heuristic_weight;
+        let beam_width = grid.opts.npc_beam_width;
+
+        let car = grid.car(car_id);
+        let car_position = car.position;
+        // no idle-roaming fallback here (unlike NearestPassenger's
+        // RandomDestination detour) -- the blanket CarAgent impl's
+        // "no path" branch already turns the car randomly for the tick,
+        // which is plenty for a car with nothing assigned to it.
+        let Some(waypoints) = NearestPassenger::waypoints_for(grid, car) else {
+            self.plan = None;
+            return;
+        };
+
+        let actions: Vec<AgentAction> = waypoints.iter().map(|(_, action)| *action).collect();
+        let precedes = move |a: usize, b: usize| {
+            matches!(
+                (actions[a], actions[b]),
+                (AgentAction::PickUp(pick), AgentAction::DropOff(drop)) if pick == drop
+            )
         };
 
-        self.path = Some(path);
+        let congestion = grid
+            .opts
+            .route_around_congestion
+            .then(|| grid.section_congestion());
+        self.plan = Some(MultiLegPath::plan(
+            car_position,
+            waypoints,
+            precedes,
+            Heuristic::Manhattan,
+            heuristic_weight,
+            congestion,
+            grid.closed_sections(),
+            beam_width,
+        ));
     }
 
     fn get_path(&self) -> Option<&Path> {
-        self.path.as_ref()
+        self.plan.as_ref().map(MultiLegPath::current_leg)
+    }
+
+    fn invalidate_path(&mut self) {
+        self.plan = None;
     }
 }
 
@@ -304,137 +1062,147 @@ impl PythonAgent {
     }
 }
 
-impl CarPathAgent for PythonAgent {
-    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
-        let py_state = grid.py_state(car_id);
-        let py_action = self.python_wrapper.get_action(py_state.clone());
+// builds the Path for `py_action` and updates the car's recent_actions/
+// active_action bookkeeping -- the actual AgentAction -> Path dispatch,
+// shared between PythonAgent (action chosen by a Python callback each
+// tick) and GymAgent (action set externally via Grid::set_gym_action) so
+// the two don't duplicate this match.
+fn path_for_py_action(grid: &mut Grid, car_id: CarId, py_action: PyAction) -> Path {
+    if let Some((_, n_closest)) = py_action.pick_up_passenger {
+        grid.stats.ticks_picking_up_n_closest_passenger[n_closest] += 1;
+    } else if let Some((_, n_closest)) = py_action.drop_off_passenger {
+        grid.stats.ticks_dropping_off_n_closest_passenger[n_closest] += 1;
+    }
 
-        let half_transition = (py_state, py_action.clone());
-        let mut guard = self.half_transitions.lock().unwrap();
-        assert!(guard.is_none());
-        *guard = Some(half_transition);
+    // we use this instead of grid.car_mut() so that we only hold the
+    // &mut on grid.cars, not the whole grid
+    let car = grid.cars.get_mut(&car_id).unwrap();
+    car.took_action(py_action.clone());
 
-        if let Some(agent) = &mut self.deterministic_agent {
-            agent.calculate_path(grid, car_id);
-            self.path = agent.get_path().cloned();
-            return;
-        }
+    let agent_action: AgentAction = py_action.clone().into();
 
-        if let Some((_, n_closest)) = py_action.pick_up_passenger {
-            grid.stats.ticks_picking_up_n_closest_passenger[n_closest] += 1;
-        } else if let Some((_, n_closest)) = py_action.drop_off_passenger {
-            grid.stats.ticks_dropping_off_n_closest_passenger[n_closest] += 1;
-        }
+    let mut path = match agent_action {
+        AgentAction::PickUp(passenger_id) => {
+            grid.stats.pick_up_requests += 1;
 
-        // we use this instead of grid.car_mut() so that we only hold the
-        // &mut on grid.cars, not the whole grid
-        let car = grid.cars.get_mut(&car_id).unwrap();
-        car.took_action(py_action.clone());
+            grid.assign_car_to_passenger(car_id, passenger_id);
 
-        let agent_action: AgentAction = py_action.into();
-        let agent_action_dbg = format!("{:?}", agent_action);
+            let passenger = grid
+                .get_idle_passenger(passenger_id)
+                .expect("Tried picking up passenger not on the grid");
 
-        let mut path = match agent_action {
-            AgentAction::PickUp(passenger_id) => {
-                grid.stats.pick_up_requests += 1;
+            let car = grid.car(car_id);
+            let path = car.find_path(passenger.start);
+            path
+        }
 
-                grid.assign_car_to_passenger(car_id, passenger_id);
+        AgentAction::DropOff(passenger_id) => {
+            grid.stats.drop_off_requests += 1;
 
-                let passenger = grid
-                    .get_idle_passenger(passenger_id)
-                    .expect("Tried picking up passenger not on the grid");
+            let passenger = car
+                .passengers
+                .iter()
+                .find_map(|p| {
+                    let CarPassenger::DroppingOff(p, _) = p else {
+                        return None;
+                    };
+                    (p.id == passenger_id).then_some(p)
+                })
+                .expect("Tried dropping off passenger not in the car");
+
+            let path = car.find_path(passenger.destination);
+            path
+        }
 
-                let car = grid.car(car_id);
-                let path = car.find_path(passenger.start);
-                path
-            }
+        AgentAction::HeadTowards(direction) => {
+            grid.stats.head_towards_requests += 1;
 
-            AgentAction::DropOff(passenger_id) => {
-                grid.stats.drop_off_requests += 1;
+            let current_road_section = car.position.road_section;
 
-                let passenger = car
-                    .passengers
-                    .iter()
-                    .find_map(|p| {
-                        let CarPassenger::DroppingOff(p) = p else {
-                            return None;
-                        };
-                        (p.id == passenger_id).then_some(p)
-                    })
-                    .expect("Tried dropping off passenger not in the car");
-
-                let path = car.find_path(passenger.destination);
-                path
-            }
+            let possible_decisions = car.position.possible_decisions();
+            let possible_next_positions = possible_decisions
+                .into_iter()
+                .filter(|d| *d != CarDecision::ChargeBattery)
+                .filter_map(|d| current_road_section.take_decision(d))
+                .collect::<Vec<_>>();
 
-            AgentAction::HeadTowards(direction) => {
-                grid.stats.head_towards_requests += 1;
+            let sort_fn = |a: &RoadSection, b: &RoadSection| {
+                let (ax, ay) = a.checkerboard_coords();
+                let (bx, by) = b.checkerboard_coords();
 
-                let current_road_section = car.position.road_section;
+                match direction {
+                    Direction::Up => ay.total_cmp(&by),
+                    Direction::Down => by.total_cmp(&ay),
+                    Direction::Left => ax.total_cmp(&bx),
+                    Direction::Right => bx.total_cmp(&ax),
+                }
+            };
 
-                let possible_decisions = car.position.possible_decisions();
-                let possible_next_positions = possible_decisions
-                    .into_iter()
-                    .filter(|d| *d != CarDecision::ChargeBattery)
-                    .filter_map(|d| current_road_section.take_decision(d))
-                    .collect::<Vec<_>>();
+            let new_road_section = possible_next_positions.into_iter().min_by(sort_fn).unwrap();
 
-                let sort_fn = |a: &RoadSection, b: &RoadSection| {
-                    let (ax, ay) = a.checkerboard_coords();
-                    let (bx, by) = b.checkerboard_coords();
+            let destination = CarPosition {
+                road_section: new_road_section,
+                position_in_section: 0,
+                in_charging_station: None,
+            };
 
-                    match direction {
-                        Direction::Up => ay.total_cmp(&by),
-                        Direction::Down => by.total_cmp(&ay),
-                        Direction::Left => ax.total_cmp(&bx),
-                        Direction::Right => bx.total_cmp(&ax),
-                    }
-                };
+            let path = car.find_path(destination);
+            path
+        }
 
-                let new_road_section = possible_next_positions.into_iter().min_by(sort_fn).unwrap();
+        AgentAction::ChargeBattery(station_id) => {
+            grid.stats.charge_requests += 1;
 
-                let destination = CarPosition {
-                    road_section: new_road_section,
-                    position_in_section: 0,
-                    in_charging_station: None,
-                };
+            let charging_station = grid.charging_stations.get(&station_id).unwrap();
 
-                let path = car.find_path(destination);
-                path
-            }
+            let positions = [
+                charging_station.entrance,
+                charging_station.entrance.other_side_of_road(),
+            ];
+            let paths = positions.iter().map(|p| car.find_path(*p));
 
-            AgentAction::ChargeBattery(station_id) => {
-                grid.stats.charge_requests += 1;
+            let path = paths.min_by_key(|p| p.cost).unwrap();
+            path
+        }
+    };
 
-                let charging_station = grid.charging_stations.get(&station_id).unwrap();
+    path.action = Some(agent_action);
 
-                let positions = [
-                    charging_station.entrance,
-                    charging_station.entrance.other_side_of_road(),
-                ];
-                let paths = positions.iter().map(|p| car.find_path(*p));
+    let car = grid.car_mut(car_id);
+    if matches!(agent_action, AgentAction::HeadTowards(_)) && car.position.position_in_section == 0
+    {
+        // the agent just reached where it wanted to HeadTowards
+        car.active_action = None;
+    } else {
+        car.active_action = Some(py_action);
+    }
 
-                let path = paths.min_by_key(|p| p.cost).unwrap();
-                path
-            }
-        };
+    path
+}
 
-        path.action = Some(agent_action);
-        self.path = Some(path);
+impl CarPathAgent for PythonAgent {
+    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        let py_state = grid.py_state(car_id);
+        let py_action = self.python_wrapper.get_action(py_state.clone());
 
-        let verbose = grid.opts.verbose;
-        let car = grid.car_mut(car_id);
+        let half_transition = (py_state, py_action.clone());
+        let mut guard = self.half_transitions.lock().unwrap();
+        assert!(guard.is_none());
+        *guard = Some(half_transition);
 
-        if matches!(agent_action, AgentAction::HeadTowards(_))
-            && car.position.position_in_section == 0
-        {
-            // the agent just reached where it wanted to HeadTowards
-            car.active_action = None;
-        } else {
-            car.active_action = Some(py_action);
+        if let Some(agent) = &mut self.deterministic_agent {
+            agent.calculate_path(grid, car_id);
+            self.path = agent.get_path().cloned();
+            return;
         }
 
+        let agent_action_dbg = format!("{:?}", AgentAction::from(&py_action));
+        let verbose = grid.opts.verbose;
+
+        self.path = Some(path_for_py_action(grid, car_id, py_action));
+
         if verbose {
+            let car = grid.car(car_id);
             let passenger_count = car
                 .passengers
                 .iter()
@@ -455,6 +1223,51 @@ impl CarPathAgent for PythonAgent {
     }
 }
 
+// drives a car from a PyAction set externally via Grid::set_gym_action,
+// rather than a Python get_action callback (PythonAgent) or an on-board
+// heuristic (NearestPassenger/RandomDestination/...) -- the car
+// PyGridEnv::step/reset control directly for the classic Gym-style
+// reset()/step() loop, alongside whatever PythonAgent-driven cars are
+// also in the same Grid.
+#[derive(Default, Debug)]
+pub struct GymAgent {
+    path: Option<Path>,
+    pending_action: Option<PyAction>,
+}
+
+impl GymAgent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // consumed by the next calculate_path -- see Grid::set_gym_action.
+    pub fn set_pending_action(&mut self, action: PyAction) {
+        self.pending_action = Some(action);
+    }
+}
+
+impl CarPathAgent for GymAgent {
+    fn calculate_path(&mut self, grid: &mut Grid, car_id: CarId) {
+        let Some(py_action) = self.pending_action.take() else {
+            // PyGridEnv::step hasn't supplied an action for this tick yet
+            // (e.g. the gym car just spawned before the first step() call)
+            // -- keep driving whatever path is already in flight instead of
+            // guessing.
+            return;
+        };
+
+        self.path = Some(path_for_py_action(grid, car_id, py_action));
+    }
+
+    fn get_path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+
+    fn as_gym_agent_mut(&mut self) -> Option<&mut GymAgent> {
+        Some(self)
+    }
+}
+
 impl std::fmt::Debug for PythonAgent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "PythonAgent")
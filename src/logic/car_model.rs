@@ -0,0 +1,94 @@
+use pyo3::prelude::*;
+
+// a per-vehicle-class parameter space, analogous to PGDrive's vehicle
+// parameter config: governs battery behaviour and render footprint so
+// fleets can mix vehicle classes instead of sharing one implicit model.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[pyclass]
+pub struct CarModel {
+    #[pyo3(get)]
+    pub name: &'static str,
+
+    #[pyo3(get)]
+    pub battery_capacity_scale: f32, // multiplies discharge/charge deltas
+    #[pyo3(get)]
+    pub base_discharge_rate: f32, // percent per tick, before capacity scaling
+    #[pyo3(get)]
+    pub max_charge_acceptance_rate: f32, // caps the station's rated charging speed
+
+    #[pyo3(get)]
+    // relative to CarRenderer::car_length(). not applied when drawing: the
+    // drawn length comes from cell_length (see CarRenderer::model_scaled_rect)
+    pub render_length: f32,
+    #[pyo3(get)]
+    pub render_width: f32, // relative to CarRenderer::car_width()
+
+    #[pyo3(get)]
+    pub cell_length: usize, // how many CarPosition cells this model occupies
+}
+
+impl CarModel {
+    pub const COMPACT: Self = Self {
+        name: "compact",
+        battery_capacity_scale: 0.7,
+        base_discharge_rate: 0.0015,
+        max_charge_acceptance_rate: 0.012,
+        render_length: 0.85,
+        render_width: 0.85,
+        cell_length: 1,
+    };
+
+    pub const SEDAN: Self = Self {
+        name: "sedan",
+        battery_capacity_scale: 1.0,
+        base_discharge_rate: 0.002,
+        max_charge_acceptance_rate: 0.01,
+        render_length: 1.0,
+        render_width: 1.0,
+        cell_length: 1,
+    };
+
+    pub const SUV: Self = Self {
+        name: "suv",
+        battery_capacity_scale: 1.4,
+        base_discharge_rate: 0.0026,
+        max_charge_acceptance_rate: 0.008,
+        render_length: 1.2,
+        render_width: 1.15,
+        cell_length: 2,
+    };
+
+    // effective per-tick discharge rate for this model
+    pub fn discharge_rate(&self) -> f32 {
+        self.base_discharge_rate / self.battery_capacity_scale
+    }
+
+    // effective charging speed at a station, capped by what this model can accept
+    pub fn charging_speed(&self, station_rated_speed: f32) -> f32 {
+        station_rated_speed.min(self.max_charge_acceptance_rate) / self.battery_capacity_scale
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::registry().into_iter().find(|m| m.name == name)
+    }
+
+    pub fn registry() -> [Self; 3] {
+        [Self::COMPACT, Self::SEDAN, Self::SUV]
+    }
+}
+
+impl Default for CarModel {
+    fn default() -> Self {
+        Self::SEDAN
+    }
+}
+
+#[pyfunction]
+pub fn car_model_by_name(name: &str) -> Option<CarModel> {
+    CarModel::by_name(name)
+}
+
+#[pyfunction]
+pub fn car_model_registry() -> Vec<CarModel> {
+    CarModel::registry().to_vec()
+}
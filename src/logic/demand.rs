@@ -0,0 +1,189 @@
+// reproducible, spatially-weighted passenger demand, modeled on A/B Street's
+// ScenarioGenerator (named origin/destination flows) and SpawnOverTime (a
+// per-tick spawn rate over a time window). this is what Scenario (see
+// scenario.rs) is drained from: ScenarioGenerator::generate turns a handful
+// of DemandEntrys into the Vec<PassengerArrival> Scenario::new expects,
+// deterministically from a seed, so the same demand pattern -- rush hour
+// from the suburbs into downtown, say -- can be replayed exactly for
+// benchmarking agents against each other.
+//
+// car demand isn't generated here: a CarDeparture needs a live CarAgent to
+// construct (see CarProps), which isn't a spatial/temporal concern the way
+// a passenger trip is -- GridOpts::npc_car_count and Scenario's existing
+// car_departures already cover spawning cars themselves.
+use macroquad::color::ORANGE;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    car::CarPosition,
+    scenario::PassengerArrival,
+    util::{Direction, RoadSection},
+};
+
+// every RoadSection whose checkerboard_coords() falls within `area`
+// (x1, y1, x2, y2) -- the exhaustive version of RoadSection::random_in_area's
+// rejection sampling, computed once up front instead of guessed at on every
+// spawn.
+fn sections_in_area(area: (f32, f32, f32, f32)) -> Vec<RoadSection> {
+    let (x1, y1, x2, y2) = area;
+    let mut sections = Vec::new();
+
+    for direction in [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ] {
+        let (road_range, section_range) = if direction.is_horizontal() {
+            (
+                y1.floor() as usize..=y2.ceil() as usize,
+                x1.floor() as usize..=x2.ceil() as usize,
+            )
+        } else {
+            (
+                x1.floor() as usize..=x2.ceil() as usize,
+                y1.floor() as usize..=y2.ceil() as usize,
+            )
+        };
+
+        for road_index in road_range {
+            if road_index > direction.max_road_index() {
+                continue;
+            }
+
+            for section_index in section_range.clone() {
+                if section_index > direction.max_section_index() {
+                    continue;
+                }
+
+                let section = RoadSection::get(direction, road_index, section_index);
+                let (x, y) = section.checkerboard_coords();
+                if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
+                    sections.push(section);
+                }
+            }
+        }
+    }
+
+    sections
+}
+
+// a named sub-region of a WeightedArea that trips should be drawn from more
+// often than the rest of the area, e.g. a stadium entrance or train station
+// during a rush-hour demand pattern.
+pub struct Hotspot {
+    pub area: (f32, f32, f32, f32),
+    pub weight: f32,
+}
+
+// every valid RoadSection inside `area`, plus a cumulative-weight index over
+// them (biased towards any overlapping hotspots), precomputed once so that
+// sampling afterwards is a single weighted draw -- a binary search over the
+// cumulative weights -- rather than Passenger::random_in_event's old
+// unbounded rejection loop over the whole area.
+pub struct WeightedArea {
+    sections: Vec<RoadSection>,
+    cumulative_weights: Vec<f32>,
+}
+
+impl WeightedArea {
+    pub fn new(area: (f32, f32, f32, f32), hotspots: &[Hotspot]) -> Self {
+        let sections = sections_in_area(area);
+        assert!(
+            !sections.is_empty(),
+            "no RoadSections found in area {area:?}"
+        );
+
+        let mut total = 0.0;
+        let cumulative_weights = sections
+            .iter()
+            .map(|section| {
+                let (x, y) = section.checkerboard_coords();
+                let weight = hotspots
+                    .iter()
+                    .filter(|hotspot| {
+                        let (hx1, hy1, hx2, hy2) = hotspot.area;
+                        x >= hx1 && x <= hx2 && y >= hy1 && y <= hy2
+                    })
+                    .fold(1.0, |acc, hotspot| f32::max(acc, hotspot.weight));
+
+                total += weight;
+                total
+            })
+            .collect();
+
+        Self {
+            sections,
+            cumulative_weights,
+        }
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> RoadSection {
+        let total_weight = *self.cumulative_weights.last().unwrap();
+        let target = rng.gen_range(0.0..total_weight);
+        let index = self
+            .cumulative_weights
+            .partition_point(|&weight| weight <= target);
+
+        self.sections[index]
+    }
+}
+
+// one origin/destination flow: trips from `origin` to `destination` are
+// rolled independently on each tick of `window`, each with probability
+// `spawn_rate_per_tick` -- the discrete-tick equivalent of A/B Street's
+// SpawnOverTime rate.
+pub struct DemandEntry {
+    pub origin: WeightedArea,
+    pub destination: WeightedArea,
+    pub spawn_rate_per_tick: f64,
+    pub window: (usize, Option<usize>), // (start tick, end tick inclusive, None = runs forever)
+}
+
+// a full reproducible demand pattern: a handful of independent flows, e.g. a
+// morning commute flow from the suburbs to downtown plus a steady
+// background trickle everywhere else.
+pub struct ScenarioGenerator {
+    pub entries: Vec<DemandEntry>,
+}
+
+impl ScenarioGenerator {
+    pub fn new(entries: Vec<DemandEntry>) -> Self {
+        Self { entries }
+    }
+
+    // deterministically rolls every entry's per-tick Bernoulli draw across
+    // 0..=last_tick and turns each hit into a PassengerArrival, ready to
+    // feed straight into Scenario::new.
+    pub fn generate(&self, seed: u64, last_tick: usize) -> Vec<PassengerArrival> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut arrivals = Vec::new();
+
+        for entry in &self.entries {
+            let end = entry.window.1.unwrap_or(last_tick).min(last_tick);
+
+            for tick in entry.window.0..=end {
+                if !rng.gen_bool(entry.spawn_rate_per_tick) {
+                    continue;
+                }
+
+                arrivals.push(PassengerArrival {
+                    tick,
+                    start: station_position(entry.origin.sample(&mut rng)),
+                    destination: station_position(entry.destination.sample(&mut rng)),
+                    colour: ORANGE,
+                });
+            }
+        }
+
+        arrivals
+    }
+}
+
+fn station_position(road_section: RoadSection) -> CarPosition {
+    CarPosition {
+        road_section,
+        position_in_section: 0,
+        in_charging_station: None,
+    }
+}
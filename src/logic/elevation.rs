@@ -0,0 +1,51 @@
+// a heightfield over the grid's checkerboard coordinates, used to make
+// energy use depend on the slope a car is climbing or descending.
+//
+// built from value noise (hashed lattice points, bilinearly interpolated
+// with a smoothstep) rather than per-tile randomness, so hills form
+// contiguous ridges a few sections wide instead of single-tile spikes.
+
+// height, in arbitrary units, at full noise amplitude
+pub const HEIGHT_SCALE: f32 = 1.0;
+
+// width, in sections, of one noise lattice cell - bigger cells make wider,
+// gentler hills
+const NOISE_CELL_SIZE: f32 = 5.0;
+
+pub fn elevation_at(checkerboard_coords: (f32, f32)) -> f32 {
+    let (x, y) = checkerboard_coords;
+    value_noise(x / NOISE_CELL_SIZE, y / NOISE_CELL_SIZE) * HEIGHT_SCALE
+}
+
+fn value_noise(x: f32, y: f32) -> f32 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+
+    let sx = smoothstep(x - x0);
+    let sy = smoothstep(y - y0);
+
+    let n00 = lattice_hash(x0 as i64, y0 as i64);
+    let n10 = lattice_hash(x0 as i64 + 1, y0 as i64);
+    let n01 = lattice_hash(x0 as i64, y0 as i64 + 1);
+    let n11 = lattice_hash(x0 as i64 + 1, y0 as i64 + 1);
+
+    let nx0 = n00 + (n10 - n00) * sx;
+    let nx1 = n01 + (n11 - n01) * sx;
+
+    nx0 + (nx1 - nx0) * sy
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// deterministic pseudo-random value in [0, 1) for a lattice point
+fn lattice_hash(x: i64, y: i64) -> f32 {
+    let mut h = x
+        .wrapping_mul(374_761_393)
+        .wrapping_add(y.wrapping_mul(668_265_263));
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    h ^= h >> 16;
+
+    (h.rem_euclid(1 << 16)) as f32 / (1 << 16) as f32
+}
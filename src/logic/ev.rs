@@ -1,12 +1,18 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use super::{
     car::{CarId, CarPosition},
-    util::RoadSection,
+    car_model::CarModel,
+    util::{HashMap, RoadSection},
 };
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct BatteryPercent(f32);
 
 impl BatteryPercent {
@@ -21,16 +27,33 @@ impl BatteryPercent {
         self.0
     }
 
-    pub fn charging(&mut self, station: &ChargingStation) {
-        let new_percent = self.0 + station.charging_speed.get();
+    pub fn charging(&mut self, station: &ChargingStation, model: &CarModel) {
+        // constant-current/constant-voltage curve: below the taper threshold,
+        // charge at the full rated speed. above it, scale the rate down
+        // linearly so the last bit of charge takes much longer, like a real EV.
+        let rated = model.charging_speed(station.charging_speed.get());
+        let taper_scale = ((1.0 - self.0) / (1.0 - station.taper_threshold)).clamp(0.0, 1.0);
+        let effective_rate = match self.0 < station.taper_threshold {
+            true => rated,
+            false => rated * taper_scale,
+        };
+
+        let new_percent = self.0 + effective_rate;
         let new_percent = new_percent.min(1.0);
 
         self.0 = new_percent;
     }
 
-    pub fn discharge(&mut self, rate: f32) {
-        let new_percent = self.0 - rate;
-        let new_percent = new_percent.max(0.0);
+    // slope is signed: positive when climbing, negative when descending
+    const SLOPE_DISCHARGE_COEFFICIENT: f32 = 1.5; // the "k" in 1 + k*slope
+    const MAX_REGEN_RATE: f32 = 0.0005; // regen is a trickle, nowhere near charging speed
+
+    pub fn discharge(&mut self, base_rate: f32, model: &CarModel, slope: f32) {
+        let slope_multiplier = 1.0 + Self::SLOPE_DISCHARGE_COEFFICIENT * slope;
+        let effective_rate = (base_rate * slope_multiplier).max(-Self::MAX_REGEN_RATE);
+
+        let new_percent = self.0 - effective_rate / model.battery_capacity_scale;
+        let new_percent = new_percent.clamp(0.0, 1.0);
 
         self.0 = new_percent;
     }
@@ -40,7 +63,7 @@ impl BatteryPercent {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[pyclass]
 pub struct ChargingStationId {
     // a charging station is defined by where it is on the map
@@ -79,20 +102,67 @@ impl std::fmt::Debug for ChargingStationId {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ChargingStation {
     pub id: ChargingStationId,
     pub entrance: CarPosition,
     pub capacity: usize,
-    pub charging_speed: BatteryPercent, // per tick
+    pub charging_speed: BatteryPercent, // per tick, at/below taper_threshold
+
+    // battery percent above which this charger's effective rate starts
+    // tapering down towards 0 as the battery approaches full (CC/CV curve)
+    pub taper_threshold: f32,
 
     pub cars: Vec<CarId>,
+
+    // cars that tried to reserve a slot while the station was full, in the
+    // order they arrived. the front is promoted into `cars` on release()
+    pub queue: VecDeque<CarId>,
+
+    // à la A/B Street's park/unpark timers: a car occupies its slot (counts
+    // against capacity/has_space()) for TIME_TO_CONNECT ticks after being
+    // granted one before it actually starts charging, and again for
+    // TIME_TO_DISCONNECT ticks after deciding to leave before the slot
+    // actually frees up. ticks remaining, keyed by car id; absence from
+    // both maps (while still in `cars`) means the car is actively charging.
+    pub connecting: HashMap<CarId, usize>,
+    pub disconnecting: HashMap<CarId, usize>,
+}
+
+// whether a reserve() call got the car an active charging slot, or put it
+// in the waiting line behind `position` other cars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationStatus {
+    Active,
+    Queued { position: usize },
 }
 
 impl ChargingStation {
+    pub const DEFAULT_TAPER_THRESHOLD: f32 = 0.8;
+
+    // rough battery percent a car is assumed to arrive with, for estimating
+    // how long a fresh reservation will take to clear. only an estimate:
+    // NearestPassenger sends cars to charge once Car::needs_charge says the
+    // nearest free station is nearly out of range, not at a fixed level
+    const ASSUMED_ARRIVAL_PERCENT: f32 = 0.1;
+
+    // plug/unplug dwell, in ticks -- see `connecting`/`disconnecting` above
+    pub const TIME_TO_CONNECT: usize = 3;
+    pub const TIME_TO_DISCONNECT: usize = 2;
+
     pub fn new(
         entrance: Option<CarPosition>, // None for random
         capacity: usize,
         charging_speed: f32,
+    ) -> Self {
+        Self::with_taper_threshold(entrance, capacity, charging_speed, Self::DEFAULT_TAPER_THRESHOLD)
+    }
+
+    pub fn with_taper_threshold(
+        entrance: Option<CarPosition>, // None for random
+        capacity: usize,
+        charging_speed: f32,
+        taper_threshold: f32,
     ) -> Self {
         let entrance = entrance.unwrap_or_else(|| CarPosition::random(rand::thread_rng()));
 
@@ -101,11 +171,125 @@ impl ChargingStation {
             entrance,
             capacity,
             charging_speed: BatteryPercent::new(charging_speed),
+            taper_threshold,
             cars: vec![],
+            queue: VecDeque::new(),
+            connecting: HashMap::default(),
+            disconnecting: HashMap::default(),
         }
     }
 
     pub fn has_space(&self) -> bool {
         self.cars.len() < self.capacity
     }
+
+    // grants the car an active slot if one's free, otherwise queues it.
+    // idempotent: calling this again for a car that's already active or
+    // already queued just returns its current status. a newly active slot
+    // starts out connecting, not charging -- see `tick` below.
+    pub fn reserve(&mut self, car_id: CarId) -> ReservationStatus {
+        if self.cars.contains(&car_id) {
+            return ReservationStatus::Active;
+        }
+
+        if let Some(position) = self.queue.iter().position(|id| *id == car_id) {
+            return ReservationStatus::Queued { position };
+        }
+
+        if self.has_space() {
+            self.cars.push(car_id);
+            self.connecting.insert(car_id, Self::TIME_TO_CONNECT);
+            ReservationStatus::Active
+        } else {
+            self.queue.push_back(car_id);
+            ReservationStatus::Queued {
+                position: self.queue.len() - 1,
+            }
+        }
+    }
+
+    // advances the connect/disconnect countdowns by one tick, returning the
+    // cars that just finished connecting (and so started actively charging
+    // this tick), for Grid::tick_cars to turn into TickEvents. call once per
+    // ChargingStation per Grid::tick_cars, before anything reads
+    // is_charging()/ready_to_leave() for this tick.
+    pub fn tick(&mut self) -> Vec<CarId> {
+        let mut just_started_charging = vec![];
+        self.connecting.retain(|&car_id, ticks_left| {
+            *ticks_left = ticks_left.saturating_sub(1);
+            let still_connecting = *ticks_left > 0;
+            if !still_connecting {
+                just_started_charging.push(car_id);
+            }
+            still_connecting
+        });
+        for ticks_left in self.disconnecting.values_mut() {
+            *ticks_left = ticks_left.saturating_sub(1);
+        }
+        just_started_charging
+    }
+
+    // a car with an active slot that isn't still plugging in or unplugging
+    // is actually drawing charge.
+    pub fn is_charging(&self, car_id: CarId) -> bool {
+        self.cars.contains(&car_id)
+            && !self.connecting.contains_key(&car_id)
+            && !self.disconnecting.contains_key(&car_id)
+    }
+
+    // the first tick a connected car's agent decides to leave, this starts
+    // its unplug countdown; the car keeps occupying the slot (and can't
+    // actually move away -- see Grid::tick_cars) until ready_to_leave().
+    // idempotent, like reserve().
+    pub fn begin_disconnect(&mut self, car_id: CarId) {
+        self.connecting.remove(&car_id);
+        self.disconnecting
+            .entry(car_id)
+            .or_insert(Self::TIME_TO_DISCONNECT);
+    }
+
+    pub fn ready_to_leave(&self, car_id: CarId) -> bool {
+        self.disconnecting.get(&car_id) == Some(&0)
+    }
+
+    // frees up the car's slot (if it had one) and promotes the next queued
+    // car into it (starting its own connect countdown). does nothing if the
+    // car wasn't reserved here.
+    pub fn release(&mut self, car_id: CarId) {
+        self.connecting.remove(&car_id);
+        self.disconnecting.remove(&car_id);
+
+        let Some(index) = self.cars.iter().position(|id| *id == car_id) else {
+            self.queue.retain(|id| *id != car_id);
+            return;
+        };
+
+        self.cars.swap_remove(index);
+        if let Some(next_car) = self.queue.pop_front() {
+            self.cars.push(next_car);
+            self.connecting.insert(next_car, Self::TIME_TO_CONNECT);
+        }
+    }
+
+    // ticks for one charging car to go from ASSUMED_ARRIVAL_PERCENT to full,
+    // at this station's rated speed. the CV taper phase isn't simulated
+    // tick-by-tick here; it's approximated as taking as long as the
+    // remaining CC phase, which lands in the right ballpark without having
+    // to walk BatteryPercent::charging() forward in a loop.
+    pub fn expected_ticks_to_free(&self) -> usize {
+        let rated = self.charging_speed.get();
+        if rated <= 0.0 {
+            return usize::MAX;
+        }
+
+        let cc_phase = (self.taper_threshold - Self::ASSUMED_ARRIVAL_PERCENT).max(0.0) / rated;
+        let cv_phase = cc_phase;
+        Self::TIME_TO_CONNECT + (cc_phase + cv_phase).ceil() as usize + Self::TIME_TO_DISCONNECT
+    }
+
+    // queue length times expected ticks-to-free: a rough wait estimate a
+    // policy can weigh against the path cost of a farther, less busy station
+    pub fn estimated_wait_ticks(&self) -> usize {
+        self.queue.len() * self.expected_ticks_to_free()
+    }
 }
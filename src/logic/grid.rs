@@ -1,22 +1,42 @@
 use core::panic;
-use std::{io::Write, mem};
+use std::{
+    io::Write,
+    mem,
+    sync::{Arc, Mutex},
+};
 
 use macroquad::color::*;
 use pyo3::prelude::*;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     logic::car::NextCarPosition,
-    python::bridge::{bridge::PythonAgentWrapper, py_grid::PyGridState},
+    python::bridge::{
+        bridge::{PyAction, PythonAgentWrapper},
+        py_grid::PyGridState,
+    },
 };
 
 use super::{
+    analytics::{Analytics, RollingStats},
     car::{Car, CarDecision, CarId, CarPassenger, CarPosition, CarProps, CarToSpawn},
-    car_agent::{NullAgent, PythonAgent, RandomTurns},
-    ev::{ChargingStation, ChargingStationId},
-    grid_util::{GridOpts, GridStats, LightState, PassengerEvent, TickEvent, TrafficLight},
+    car_agent::{
+        CarPathAgent, CentralDispatch, Detour, Dispatcher, FixedRoute, NullAgent, PythonAgent,
+        RandomTurns, Route,
+    },
+    demand::ScenarioGenerator,
+    ev::{ChargingStation, ChargingStationId, ReservationStatus},
+    grid_util::{
+        GridOpts, GridStats, LightState, PassengerEvent, SectionCongestion, TickEvent,
+        TrafficLight,
+    },
+    intersection::{IntersectionControl, IntersectionId, IntersectionReservationPolicy},
     passenger::{Passenger, PassengerId},
-    util::{hashmap_with_capacity, HashMap, HashSet, Orientation, RoadSection},
+    pathfinding::{Heuristic, Landmarks, Path, SectionDistances},
+    rail::{RailLine, RailLineId, Train, TrainId},
+    scenario::{passenger_from_arrival, Scenario},
+    snapshot::{CarSnapshot, GridSnapshot},
+    util::{hashmap_with_capacity, Direction, DrivingSide, HashMap, HashSet, Orientation, RoadSection},
 };
 
 pub struct Grid {
@@ -30,14 +50,77 @@ pub struct Grid {
 
     pub cars_to_spawn: Vec<CarToSpawn>,
 
+    // reproducible timed demand script, if one was set via set_scenario;
+    // drained each tick by tick_scenario instead of the ad-hoc random
+    // spawning add_car/generate_passenger otherwise do
+    pub scenario: Option<Scenario>,
+
     pub traffic_lights: HashMap<RoadSection, TrafficLight>,
+
+    // the actual authority gating car decisions at each intersection (see
+    // Grid::movement_blocked); traffic_lights above is kept only for the
+    // renderer's simpler red/green-per-section visualisation and no longer
+    // drives car behaviour.
+    pub intersection_controls: HashMap<IntersectionId, IntersectionControl>,
+
+    // movements already granted this tick at each intersection under
+    // IntersectionControl::Reservation, keyed by IntersectionId; reset to
+    // empty at the start of every tick_cars and filled in live as
+    // movement_blocked grants each car's requested movement. see
+    // IntersectionReservationPolicy's doc comment for why this is rebuilt
+    // from scratch each tick rather than held across many.
+    intersection_reservations: HashMap<IntersectionId, Vec<(CarId, Direction, CarDecision)>>,
+
     pub charging_stations: HashMap<ChargingStationId, ChargingStation>,
+    section_congestion: HashMap<RoadSection, SectionCongestion>,
+
+    // sections currently closed to traffic -- hard-excluded from
+    // Path::find_weighted/MultiLegPath::plan's search rather than merely
+    // penalised the way section_congestion is. see close_section/open_section.
+    closed_sections: HashSet<RoadSection>,
+
+    // the rail/tram layer running alongside the road grid (see logic::rail
+    // for why this is a fixed, always-on default network rather than
+    // GridOpts-configurable like charging_stations: wiring it through would
+    // mean breaking GridOpts::new's existing positional Python constructor,
+    // which is out of scope for adding the layer itself).
+    pub rail_lines: HashMap<RailLineId, RailLine>,
+    pub trains: HashMap<TrainId, Train>,
+
+    // per-section car queue, cars sorted ascending by position_in_section
+    // (so the last entry is the one closest to the intersection). rebuilt
+    // every tick, before any car moves, and used to enforce car-following
+    // (Grid::gap_to_leader) the same way section_congestion enforces speed.
+    section_queues: HashMap<RoadSection, Vec<CarId>>,
+
+    // ALT heuristic tables for Path::find, precomputed once since the
+    // section graph never changes after construction
+    pub landmarks: Landmarks,
+
+    // all-pairs section distance table, precomputed once (like landmarks
+    // above) when GridOpts::precompute_section_distances is set; None keeps
+    // Grid::section_distance falling back to a lazy Path::find per call
+    section_distances: Option<SectionDistances>,
+
+    // rolling-window throughput/latency/utilization aggregates, read via
+    // Grid::rolling_stats -- see analytics::Analytics
+    analytics: Analytics,
 
     pub ticks_passed: usize,
     pub stats: GridStats,
 
     pub tick_state: Option<PyGridState>,
     pub tick_events: Vec<TickEvent>,
+
+    // per-subsystem child RNGs derived from GridOpts::seed at construction
+    // (see Grid::new), so passenger generation and car respawning are each
+    // independently reproducible, A/B Street-scenario-generation-style,
+    // rather than sharing a single stream whose draw order would make one
+    // subsystem's output depend on how many draws the other one happens to
+    // make. NOT every random call site in the codebase goes through these --
+    // see Grid::scenario_seed's doc comment for the ones that still don't.
+    passenger_rng: StdRng,
+    car_rng: StdRng,
 }
 
 impl Grid {
@@ -49,6 +132,9 @@ impl Grid {
     pub const TRAFFIC_LIGHT_TOGGLE_TICKS: usize = 60; // 3s at 20TPS
 
     pub const CAR_SPEED: usize = 3;
+    // battery percent per tick burned idling in a jammed section (much less
+    // than actually driving costs)
+    const JAM_IDLE_DISCHARGE_RATE: f32 = 0.0005;
     // pub const CAR_DISCHARGE_RATE: f32 = 0.002; // can go 500 ticks without charging
 
     // pub const MAX_TOTAL_PASSENGERS: usize = Self::HORIZONTAL_ROADS * Self::VERTICAL_ROADS;
@@ -58,17 +144,29 @@ impl Grid {
     pub fn new(opts: GridOpts, python_agents: Vec<PythonAgentWrapper>) -> Self {
         assert_eq!(opts.agent_car_count, python_agents.len() as u32);
 
+        DrivingSide::set_current(opts.driving_side);
+
         let traffic_lights = Self::generate_traffic_lights();
+        let intersection_controls = Self::generate_intersection_controls();
         let charging_stations = Self::generate_charging_stations(
             &opts.charging_stations,
             opts.charging_station_capacity,
         );
+        let (rail_lines, trains) = Self::generate_rail_network();
+        let landmarks = Landmarks::compute();
+        let section_distances = opts
+            .precompute_section_distances
+            .then(SectionDistances::compute);
 
         let mut stats = GridStats::default();
         stats.ticks_with_n_passengers = vec![0; opts.passengers_per_car + 1];
         stats.ticks_picking_up_n_closest_passenger = vec![0; opts.passenger_radius + 1];
         stats.ticks_dropping_off_n_closest_passenger = vec![0; opts.passenger_radius + 1];
 
+        let mut master_rng = StdRng::seed_from_u64(opts.seed);
+        let passenger_rng = StdRng::seed_from_u64(master_rng.gen());
+        let car_rng = StdRng::seed_from_u64(master_rng.gen());
+
         let mut this = Self {
             opts: opts.clone(),
 
@@ -79,15 +177,29 @@ impl Grid {
             waiting_passenger_positions: HashMap::default(),
 
             cars_to_spawn: Vec::new(),
+            scenario: None,
 
             traffic_lights,
+            intersection_controls,
+            intersection_reservations: HashMap::default(),
             charging_stations,
+            section_congestion: HashMap::default(),
+            closed_sections: HashSet::default(),
+            section_queues: HashMap::default(),
+            rail_lines,
+            trains,
+            landmarks,
+            section_distances,
+            analytics: Analytics::new(opts.time_series_bucket_ticks),
 
             ticks_passed: 0,
             stats,
 
             tick_state: None,
             tick_events: Vec::new(),
+
+            passenger_rng,
+            car_rng,
         };
 
         // spawn passengers
@@ -99,6 +211,22 @@ impl Grid {
             this.add_car(npc_props, None);
         }
 
+        // spawn the central-dispatch fleet, if requested: every one of
+        // these cars shares the same Arc<Mutex<Dispatcher>>, so they're
+        // jointly planned as one fleet (see CentralDispatch's doc comment)
+        // rather than each deciding independently like NearestPassenger/
+        // RandomTurns cars do.
+        let dispatcher = Arc::new(Mutex::new(Dispatcher::new()));
+        for _ in 0..opts.central_dispatch_car_count {
+            let dispatch_props = CarProps::new(
+                CentralDispatch::new(dispatcher.clone()),
+                Self::CAR_SPEED,
+                0.0,
+                SKYBLUE,
+            );
+            this.add_car(dispatch_props, None);
+        }
+
         // spawn required agent cars
         let mut python_agents = python_agents.into_iter();
         let agent_car_colours = [RED, GREEN, ORANGE, PURPLE, PINK];
@@ -113,6 +241,15 @@ impl Grid {
             this.add_car(agent_props, None);
         }
 
+        // spawn scheduled-transit shuttles, one FixedRoute car per
+        // configured route, starting at the route's first stop
+        for route in &opts.shuttle_routes {
+            let first_stop = route.stops[0];
+            let shuttle_props =
+                CarProps::new(FixedRoute::new(route.clone()), Self::CAR_SPEED, 0.0, GOLD);
+            this.add_car(shuttle_props, Some(first_stop));
+        }
+
         this
     }
 
@@ -135,6 +272,23 @@ impl Grid {
         traffic_lights
     }
 
+    // a sensible default: every intersection in the grid gets its own
+    // two-phase signal (IntersectionControl::default_signal), keyed by the
+    // corner each RoadSection leads into so all 2-4 approaches sharing a
+    // corner share one control.
+    fn generate_intersection_controls() -> HashMap<IntersectionId, IntersectionControl> {
+        let mut intersection_controls = HashMap::default();
+
+        for section in RoadSection::all() {
+            let id = IntersectionId::entering(section);
+            intersection_controls
+                .entry(id)
+                .or_insert_with(|| IntersectionControl::default_signal(Self::TRAFFIC_LIGHT_TOGGLE_TICKS));
+        }
+
+        intersection_controls
+    }
+
     fn generate_charging_stations(
         coords: &[CarPosition],
         capacity: usize,
@@ -146,17 +300,38 @@ impl Grid {
             .collect()
     }
 
+    // one demo tram line running the length of the top horizontal road,
+    // there-and-back between its two ends, with a single two-cell tram
+    // cycling it. see the comment on Grid::rail_lines for why this is a
+    // fixed default rather than GridOpts-configurable for now.
+    const RAIL_DWELL_TICKS: usize = 20;
+
+    fn generate_rail_network() -> (HashMap<RailLineId, RailLine>, HashMap<TrainId, Train>) {
+        let stops = vec![
+            RoadSection::get(Direction::Right, 0, 0),
+            RoadSection::get(Direction::Right, 0, Self::VERTICAL_ROADS - 2),
+        ];
+        let line = RailLine::new(stops, false);
+
+        let train = Train::new(&line, 2, 4, Self::RAIL_DWELL_TICKS);
+
+        let rail_lines = HashMap::from_iter([(line.id, line)]);
+        let trains = HashMap::from_iter([(train.id, train)]);
+        (rail_lines, trains)
+    }
+
     fn generate_passengers(&mut self) {
         assert_eq!(self.ticks_passed, 0);
 
         let count = self.opts.initial_passenger_count;
         self.waiting_passengers = hashmap_with_capacity(count as usize);
 
-        let mut rng = rand::thread_rng();
+        let mut rng = mem::replace(&mut self.passenger_rng, StdRng::from_entropy());
         for _ in 0..count {
             let passenger = self.generate_passenger(&mut rng);
             self.waiting_passengers.insert(passenger.id, passenger);
         }
+        self.passenger_rng = rng;
     }
 
     fn current_passenger_event(&self) -> Option<&PassengerEvent> {
@@ -249,6 +424,213 @@ impl Grid {
         self.cars_to_spawn.push(car_to_spawn);
     }
 
+    // sets the PyAction a gym-controlled car's GymAgent will act on next
+    // calculate_path -- the entry point PyGridEnv::step uses to inject an
+    // externally-chosen action before ticking, mirroring how PythonAgent's
+    // get_action callback supplies one for Python-driven cars each tick.
+    // panics if `car_id` isn't driven by a GymAgent.
+    pub fn set_gym_action(&mut self, car_id: CarId, action: PyAction) {
+        let car = self.car_mut(car_id);
+        let agent = car
+            .props
+            .agent
+            .as_gym_agent_mut()
+            .expect("set_gym_action called on a car that isn't driven by a GymAgent");
+        agent.set_pending_action(action);
+    }
+
+    // replaces add_car/generate_passenger's random spawning for the rest of
+    // this run: every departure/arrival in `scenario` is drained by
+    // tick_scenario on its scheduled tick instead
+    pub fn set_scenario(&mut self, scenario: Scenario) {
+        self.scenario = Some(scenario);
+    }
+
+    // like new, but seeded with a pre-built Scenario's demand script from
+    // tick 0 instead of relying on the usual random spawning -- equivalent
+    // to calling new then set_scenario, for callers (e.g. a policy-variant
+    // benchmark replaying a saved scenario) that want the scripted demand
+    // in place before the very first tick.
+    pub fn from_scenario(
+        opts: GridOpts,
+        python_agents: Vec<PythonAgentWrapper>,
+        scenario: Scenario,
+    ) -> Self {
+        let mut this = Self::new(opts, python_agents);
+        this.set_scenario(scenario);
+        this
+    }
+
+    // the demand.rs equivalent of from_scenario: rolls `generator`'s
+    // origin/destination flows (see ScenarioGenerator::generate) into a
+    // Vec<PassengerArrival>, feeds that straight into Scenario::new, and
+    // seeds the new Grid with the result from tick 0 -- for benchmarking a
+    // hand-authored demand pattern (rush hour from the suburbs into
+    // downtown, say) against a fixed seed instead of hand-building the
+    // arrivals Scenario::new expects.
+    pub fn from_demand_generator(
+        opts: GridOpts,
+        python_agents: Vec<PythonAgentWrapper>,
+        generator: &ScenarioGenerator,
+        seed: u64,
+        last_tick: usize,
+    ) -> Self {
+        let arrivals = generator.generate(seed, last_tick);
+        let scenario = Scenario::new(seed, Vec::new(), arrivals, &[]);
+        Self::from_scenario(opts, python_agents, scenario)
+    }
+
+    // the seed behind this run's Scenario demand script, if one is set.
+    // replaying a PyGridState snapshot with the same seed reproduces the
+    // same scripted car departures/passenger arrivals from tick 0 -- it does
+    // NOT resume the Scenario's RNG stream from wherever it had gotten to
+    // when the snapshot was taken. see GridOpts::seed/Grid::passenger_rng
+    // and Grid::car_rng for the separate master seed covering the grid's
+    // own passenger placement and car respawning; a handful of call sites
+    // still fall outside both seeds -- landmark selection (pathfinding.rs)
+    // and charging station entrance pick (ev.rs) still draw from unseeded
+    // rand::thread_rng(). full tick-for-tick resume would mean seeding and
+    // persisting those too, which this commit doesn't attempt.
+    pub fn scenario_seed(&self) -> Option<u64> {
+        self.scenario.as_ref().map(Scenario::seed)
+    }
+
+    // the master seed this grid's passenger/car RNGs were derived from (see
+    // GridOpts::seed) -- exposing it lets an episode be replayed exactly by
+    // constructing a fresh GridOpts with the same seed, the same way
+    // scenario_seed lets a Scenario's demand script be replayed.
+    pub fn seed(&self) -> u64 {
+        self.opts.seed
+    }
+
+    // lets car_agent's NPC agents (RandomTurns, RandomDestination) draw
+    // from the grid's seeded car_rng instead of unseeded rand::thread_rng(),
+    // so their turn/destination choices are reproducible from GridOpts::seed
+    // like passenger placement and car respawning already are -- see
+    // scenario_seed's doc comment for the replay gaps this closes.
+    pub(crate) fn car_rng_mut(&mut self) -> &mut StdRng {
+        &mut self.car_rng
+    }
+
+    // a point-in-time copy of this grid's per-tick-mutated state, for
+    // MCTS-style rollout branching: snapshot once, try several action
+    // sequences via tick(), restore() back and try again. only meaningful
+    // as input to a later restore() call on this same Grid -- it
+    // deliberately doesn't carry enough to reconstruct cars' agents
+    // (CarProps.agent) from scratch, since a PythonAgent wraps a live
+    // PyObject that has to already be attached to play that role. see
+    // GridSnapshot's doc comment for the rest of what is/isn't captured.
+    pub fn snapshot(&self) -> GridSnapshot {
+        assert!(
+            self.cars_to_spawn.is_empty(),
+            "Grid::snapshot doesn't capture cars_to_spawn (it holds a CarProps, \
+             which isn't round-trippable) -- call it between ticks, once \
+             everything queued to spawn has spawned"
+        );
+
+        let cars = self
+            .cars
+            .iter()
+            .map(|(&id, car)| {
+                let snapshot = CarSnapshot {
+                    position: car.position,
+                    ticks_until_next_movement: car.ticks_until_next_movement,
+                    passengers: car.passengers.clone(),
+                    battery: car.battery,
+                    recent_actions: car.recent_actions.clone(),
+                    blocked_by_leader: car.blocked_by_leader,
+                };
+                (id, snapshot)
+            })
+            .collect();
+
+        GridSnapshot {
+            cars,
+            car_positions: self.car_positions.iter().map(|(&p, &id)| (p, id)).collect(),
+            waiting_passengers: self
+                .waiting_passengers
+                .iter()
+                .map(|(&id, p)| (id, p.clone()))
+                .collect(),
+            waiting_passenger_positions: self
+                .waiting_passenger_positions
+                .iter()
+                .map(|(&p, &id)| (p, id))
+                .collect(),
+            traffic_lights: self
+                .traffic_lights
+                .iter()
+                .map(|(&s, l)| (s, l.clone()))
+                .collect(),
+            charging_stations: self
+                .charging_stations
+                .iter()
+                .map(|(&id, cs)| (id, cs.clone()))
+                .collect(),
+            trains: self
+                .trains
+                .iter()
+                .map(|(&id, train)| (id, train.clone()))
+                .collect(),
+            scenario: self.scenario.as_ref().map(Scenario::snapshot),
+            ticks_passed: self.ticks_passed,
+            stats: self.stats.clone(),
+            // drawn from a throwaway clone, so snapshotting doesn't itself
+            // consume anything from the live RNGs -- see GridSnapshot's doc
+            // comment for why this is a reseed rather than an exact resume.
+            passenger_rng_reseed: self.passenger_rng.clone().gen(),
+            car_rng_reseed: self.car_rng.clone().gen(),
+        }
+    }
+
+    // restores state captured by a prior snapshot() call on this same Grid.
+    // panics if the live car set doesn't match the snapshot's: restore only
+    // overwrites each existing car's moving parts (position, battery,
+    // passengers, ...), it never adds/removes a car or touches
+    // CarProps.agent, for the same reason snapshot() doesn't capture it.
+    pub fn restore(&mut self, snapshot: GridSnapshot) {
+        assert_eq!(
+            self.cars.len(),
+            snapshot.cars.len(),
+            "Grid::restore's snapshot was taken of a different set of cars"
+        );
+        for (id, car_snapshot) in snapshot.cars {
+            let car = self.car_mut(id);
+            car.position = car_snapshot.position;
+            car.ticks_until_next_movement = car_snapshot.ticks_until_next_movement;
+            car.passengers = car_snapshot.passengers;
+            car.battery = car_snapshot.battery;
+            car.recent_actions = car_snapshot.recent_actions;
+            car.blocked_by_leader = car_snapshot.blocked_by_leader;
+        }
+
+        self.car_positions = snapshot.car_positions.into_iter().collect();
+        self.waiting_passengers = snapshot.waiting_passengers.into_iter().collect();
+        self.waiting_passenger_positions =
+            snapshot.waiting_passenger_positions.into_iter().collect();
+        self.traffic_lights = snapshot.traffic_lights.into_iter().collect();
+        self.charging_stations = snapshot.charging_stations.into_iter().collect();
+
+        for (id, train_snapshot) in snapshot.trains {
+            self.trains.insert(id, train_snapshot);
+        }
+
+        match (&mut self.scenario, snapshot.scenario) {
+            (Some(scenario), Some(scenario_snapshot)) => scenario.restore(scenario_snapshot),
+            (None, None) => {}
+            _ => panic!(
+                "Grid::restore's snapshot was taken of a Grid with a different \
+                 scenario presence (Some vs None) than this one"
+            ),
+        }
+
+        self.ticks_passed = snapshot.ticks_passed;
+        self.stats = snapshot.stats;
+
+        self.passenger_rng = StdRng::seed_from_u64(snapshot.passenger_rng_reseed);
+        self.car_rng = StdRng::seed_from_u64(snapshot.car_rng_reseed);
+    }
+
     pub fn has_car_at(&self, position: &CarPosition) -> bool {
         self.car_positions.contains_key(position)
     }
@@ -257,6 +639,183 @@ impl Grid {
         &self.traffic_lights[section]
     }
 
+    pub fn intersection_control_at(&self, section: &RoadSection) -> &IntersectionControl {
+        &self.intersection_controls[&IntersectionId::entering(*section)]
+    }
+
+    // replaces the IntersectionControl governing every approach into the
+    // corner `section` leads towards (see IntersectionId::entering) -- e.g.
+    // to switch an intersection from the default signal to a stop sign or a
+    // Reservation policy at runtime.
+    pub fn set_intersection_policy(&mut self, section: RoadSection, policy: IntersectionControl) {
+        self.intersection_controls
+            .insert(IntersectionId::entering(section), policy);
+    }
+
+    pub fn congestion_at(&self, section: &RoadSection) -> SectionCongestion {
+        self.section_congestion
+            .get(section)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn section_congestion(&self) -> &HashMap<RoadSection, SectionCongestion> {
+        &self.section_congestion
+    }
+
+    pub fn closed_sections(&self) -> &HashSet<RoadSection> {
+        &self.closed_sections
+    }
+
+    // closes `section` to traffic: hard-excluded from every path search from
+    // now on (see closed_sections' doc comment), not merely penalised like
+    // congestion. any car whose currently planned path drives through it has
+    // invalidate_path called on its agent, so an agent that overrides it
+    // replans around the closure next tick instead of driving on with a
+    // now-impossible route; the rest keep their path (see
+    // CarPathAgent::invalidate_path). also fires PassengerStartUnreachable
+    // for any waiting passenger whose start is in `section` -- see
+    // TickEvent::PassengerStartUnreachable's doc comment for why this is
+    // only an approximation, not a full reachability check.
+    //
+    // cars already committed into an intersection (mid-turn into `section`)
+    // finish that move regardless: closing a section only removes it from
+    // future successor/decision choices, it doesn't eject a car already
+    // occupying it.
+    pub fn close_section(&mut self, section: RoadSection) {
+        if !self.closed_sections.insert(section) {
+            return;
+        }
+        self.tick_events.push(TickEvent::SectionClosed(section));
+
+        for car in self.cars.values_mut() {
+            let invalidate = car
+                .props
+                .agent
+                .as_path_agent()
+                .and_then(CarPathAgent::get_path)
+                .is_some_and(|path| path.sections.contains(&section));
+            if invalidate {
+                if let Some(path_agent) = car.props.agent.as_path_agent_mut() {
+                    path_agent.invalidate_path();
+                }
+            }
+        }
+
+        for passenger in self.waiting_passengers.values() {
+            if passenger.start.road_section == section {
+                self.tick_events
+                    .push(TickEvent::PassengerStartUnreachable(passenger.id));
+            }
+        }
+    }
+
+    pub fn open_section(&mut self, section: RoadSection) {
+        if self.closed_sections.remove(&section) {
+            self.tick_events.push(TickEvent::SectionReopened(section));
+        }
+    }
+
+    // throughput/latency/utilization aggregates over the last `window_ticks`
+    // ticks (clamped to however much history Analytics has kept) -- see
+    // analytics::Analytics.
+    pub fn rolling_stats(&self, window_ticks: usize) -> RollingStats {
+        self.analytics.rolling_stats(window_ticks)
+    }
+
+    pub fn analytics(&self) -> &Analytics {
+        &self.analytics
+    }
+
+    // how many cars are queued in `section`, as of the start of this tick.
+    // this is the same car count section_congestion's occupancy is derived
+    // from, so Path::find_weighted's route_around_congestion already makes
+    // pathfinding cost route around long queues, not just raw car counts.
+    pub fn queue_length(&self, section: &RoadSection) -> usize {
+        self.section_queues.get(section).map_or(0, Vec::len)
+    }
+
+    // shortest-path section distance from `a` to `b`, in cells; looks it up
+    // in the precomputed table when GridOpts::precompute_section_distances
+    // was set, else falls back to a lazy Path::find per call.
+    pub fn section_distance(&self, a: RoadSection, b: RoadSection) -> usize {
+        match &self.section_distances {
+            Some(table) => table.get(a, b),
+            None => {
+                let start = CarPosition {
+                    road_section: a,
+                    position_in_section: 0,
+                    in_charging_station: None,
+                };
+                let destination = CarPosition {
+                    road_section: b,
+                    position_in_section: 0,
+                    in_charging_station: None,
+                };
+                Path::find(start, destination, Heuristic::Alt(&self.landmarks)).cost
+            }
+        }
+    }
+
+    // the gap, in cells, between `candidate` and the rear bumper of the
+    // nearest other car at or ahead of it in `candidate.road_section`, per
+    // the pre-move queue snapshot; None if there's no such car. 0 means a
+    // car's rear already sits in `candidate`'s cell; this is also what gates
+    // intersections, since entering a section always lands on
+    // position_in_section 0.
+    pub fn gap_to_leader(&self, exclude: CarId, candidate: CarPosition) -> Option<usize> {
+        let queue = self.section_queues.get(&candidate.road_section)?;
+
+        queue
+            .iter()
+            .filter(|&&id| id != exclude)
+            .map(|&id| {
+                let leader = self.car(id);
+                leader
+                    .position
+                    .occupied_cells(leader.props.length)
+                    .map_or(leader.position.position_in_section, |span| *span.start())
+            })
+            .filter(|&rear| rear >= candidate.position_in_section)
+            .min()
+            .map(|rear| rear - candidate.position_in_section)
+    }
+
+    // position.possible_decisions(), minus any decision whose destination
+    // RoadSection is already at capacity per this tick's section queues --
+    // so an agent doesn't even consider advancing into a fully occupied
+    // section. this is coarser than gap_to_leader (section-level, not
+    // per-car), which still applies afterwards in tick_cars; falls back to
+    // the unfiltered list if every option is full, since the car has to
+    // decide something and gap_to_leader will hold it back regardless.
+    pub fn possible_decisions_avoiding_full_sections(
+        &self,
+        position: CarPosition,
+    ) -> Vec<CarDecision> {
+        let all = position.possible_decisions();
+
+        if position.is_at_charging_station() {
+            return all;
+        }
+
+        let open: Vec<CarDecision> = all
+            .iter()
+            .copied()
+            .filter(|&decision| {
+                let next = position.take_decision(decision);
+                !self.closed_sections.contains(&next.road_section)
+                    && self.queue_length(&next.road_section)
+                        < next.road_section.direction.section_capacity()
+            })
+            .collect();
+
+        if open.is_empty() {
+            all
+        } else {
+            open
+        }
+    }
+
     pub fn charging_station_entrance_at(&self, pos: CarPosition) -> Option<&ChargingStation> {
         let id1 = ChargingStationId::from(pos);
         let id2 = ChargingStationId::from(pos.other_side_of_road());
@@ -279,10 +838,34 @@ impl Grid {
         let tick_state = PyGridState::build(self);
         self.tick_state = Some(tick_state);
         self.tick_events.clear();
+        self.analytics.begin_tick();
 
         self.tick_traffic_lights();
+        self.tick_intersection_controls();
+        self.tick_congestion();
+        self.tick_section_queues();
+        self.tick_charging_stations();
+        self.tick_scenario();
         self.tick_cars();
         self.tick_passengers();
+        self.tick_trains();
+
+        let busy_cars = self
+            .cars
+            .values()
+            .filter(|car| !car.passengers.is_empty())
+            .count();
+        self.analytics
+            .record_car_utilization(busy_cars, self.cars.len());
+
+        let (charging_cars, charging_capacity) = self
+            .charging_stations
+            .values()
+            .fold((0, 0), |(cars, cap), cs| {
+                (cars + cs.cars.len(), cap + cs.capacity)
+            });
+        self.analytics
+            .record_charging_utilization(charging_cars, charging_capacity);
 
         self.tick_state = None;
         self.ticks_passed += 1;
@@ -308,24 +891,109 @@ impl Grid {
         }
     }
 
+    fn tick_intersection_controls(&mut self) {
+        for control in self.intersection_controls.values_mut() {
+            control.tick();
+        }
+    }
+
+    // recompute each section's occupancy/speed-factor from where cars are
+    // right now, before anyone moves this tick
+    fn tick_congestion(&mut self) {
+        let mut occupancy_counts: HashMap<RoadSection, usize> =
+            hashmap_with_capacity(self.cars.len());
+
+        for car in self.cars() {
+            // parked cars aren't occupying road capacity
+            if car.position.is_at_charging_station() {
+                continue;
+            }
+
+            *occupancy_counts
+                .entry(car.position.road_section)
+                .or_insert(0) += 1;
+        }
+
+        let mut section_congestion = hashmap_with_capacity(occupancy_counts.len());
+        for (section, count) in occupancy_counts {
+            let occupancy = count as f32 / section.direction.section_capacity() as f32;
+            section_congestion.insert(section, SectionCongestion::from_occupancy(occupancy));
+        }
+
+        self.section_congestion = section_congestion;
+    }
+
+    // rebuild each section's car queue (ascending by position_in_section)
+    // from where cars are right now, before anyone moves this tick. mirrors
+    // tick_congestion's "snapshot, then move" ordering so gap_to_leader sees
+    // consistent pre-move positions throughout tick_cars.
+    fn tick_section_queues(&mut self) {
+        let mut section_queues: HashMap<RoadSection, Vec<CarId>> =
+            hashmap_with_capacity(self.cars.len());
+
+        for car in self.cars() {
+            if car.position.is_at_charging_station() {
+                continue;
+            }
+
+            section_queues
+                .entry(car.position.road_section)
+                .or_default()
+                .push(car.id());
+        }
+
+        for queue in section_queues.values_mut() {
+            queue.sort_by_key(|&id| self.car_position(id).position_in_section);
+        }
+
+        self.section_queues = section_queues;
+    }
+
+    // advance each charging station's connect/disconnect countdowns, before
+    // tick_cars reads is_charging()/ready_to_leave() for this tick.
+    fn tick_charging_stations(&mut self) {
+        for cs in self.charging_stations.values_mut() {
+            let cs_id = cs.id;
+            for car_id in cs.tick() {
+                self.tick_events
+                    .push(TickEvent::CarStartedCharging(car_id, cs_id));
+            }
+        }
+    }
+
     fn tick_cars(&mut self) {
         // move all the cars in the grid
         // this is done in 2 passes: first we calculate which cars want to move
         // where, while checking two cars don't want to move to the same place.
         // then we actually move them in phase 2.
 
+        // movements granted under IntersectionControl::Reservation are
+        // accepted live as cars are processed below, so start each tick with
+        // a clean slate (see intersection_reservations' doc comment).
+        self.intersection_reservations.clear();
+
         // to double check we don't lose cars
         let cars_count = self.cars.len();
         let new_cars_count = self.cars_to_spawn.len();
 
-        // map of before-and-after positions
+        // map of car -> next position. keyed by car id rather than the
+        // current position, since cars parked at the same charging station
+        // compare equal as a CarPosition (see CarPosition::eq)
         let mut cars_to_move = hashmap_with_capacity(self.cars.len());
 
         // map of after positions, to see if another car is already moving there
         let mut next_positions = hashmap_with_capacity(self.cars.len());
 
-        // set of before positions, to easily check for car presence at coords
-        let old_positions = self.cars().map(|car| car.position).collect::<HashSet<_>>();
+        // (position_in_section, length) of every car that has already
+        // decided to move into a section this tick, grouped by that
+        // section, so a longer car further behind can tell whether it'd
+        // overlap a car that's already claimed the cells ahead of it
+        let mut next_section_occupants: HashMap<RoadSection, Vec<(usize, usize)>> =
+            hashmap_with_capacity(self.cars.len());
+
+        // whether each car was held back this tick by the car ahead of it
+        // being within min_following_gap, purely for Car::blocked_by_leader
+        let mut blocked_by_leader = hashmap_with_capacity(self.cars.len());
 
         let car_ids = self.cars.keys().copied().collect::<Vec<_>>();
         for car_id in car_ids {
@@ -351,9 +1019,11 @@ impl Grid {
                 decision
             };
 
-            let next_position = 'next_pos: {
-                // if the car is at a red light, sit still
-                if self.is_red_traffic_light(&old_position) {
+            let mut next_position = 'next_pos: {
+                // if the intersection ahead forbids this movement right now
+                // (red/non-priority signal phase, unyielded stop sign), sit
+                // still
+                if self.movement_blocked(car_id, &old_position, decision) {
                     break 'next_pos old_position;
                 }
 
@@ -365,15 +1035,96 @@ impl Grid {
                 let car = self.car(car_id);
                 let next_position = car.next_position(decision, neighbour_cs);
 
+                // a one-way section can't be entered against its allowed direction
+                let road_section = next_position.road_section;
+                if !road_section.road_type().allows_direction(road_section.direction) {
+                    break 'next_pos old_position;
+                }
+
                 let car_passenger_count = car.passengers.len();
                 self.stats.ticks_with_n_passengers[car_passenger_count] += 1;
 
-                // if there is a car already there -> don't move there, cause that
-                // car might not move (e.g. red light)
-                // if there will be a car there next turn -> don't move either
-                if old_positions.contains(&next_position)
-                    || next_positions.contains_key(&next_position)
+                // entering (or staying in) a charging station is governed by
+                // reserve(), not the generic "car already there" check below:
+                // all cars at the same station compare equal as a
+                // CarPosition (see CarPosition::eq), so that check can't
+                // tell occupied slots apart from each other
+                if let Some(cs_id) = next_position.in_charging_station {
+                    if old_position.in_charging_station != Some(cs_id) {
+                        let cs = self.charging_stations.get_mut(&cs_id).unwrap();
+                        let already_known = cs.cars.contains(&car_id) || cs.queue.contains(&car_id);
+                        let status = cs.reserve(car_id);
+                        if !already_known && matches!(status, ReservationStatus::Queued { .. }) {
+                            self.tick_events
+                                .push(TickEvent::CarQueuedForCharging(car_id, cs_id));
+                        }
+                        if !matches!(status, ReservationStatus::Active) {
+                            break 'next_pos old_position;
+                        }
+                    }
+
+                    break 'next_pos next_position;
+                } else if let Some(cs_id) = old_position.in_charging_station {
+                    // the car's agent wants to leave the charging station
+                    // (the only other decisions available at one are
+                    // TurnLeft/TurnRight -- see CarPosition::possible_decisions),
+                    // but it can't actually pull away until its unplug
+                    // countdown finishes: it keeps blocking the slot, the
+                    // same way a red light blocks a car on the road.
+                    let cs = self.charging_stations.get_mut(&cs_id).unwrap();
+                    cs.begin_disconnect(car_id);
+                    if !cs.ready_to_leave(car_id) {
+                        break 'next_pos old_position;
+                    }
+                }
+
+                // if the cells this car would occupy at next_position are
+                // already occupied by another car -> don't move there,
+                // cause that car might not move (e.g. red light)
+                // if they will be occupied by another car next turn ->
+                // don't move either. length-aware, so a long vehicle can't
+                // partially overlap a car ahead of it, or one already
+                // moving into the cells it wants.
+                let length = car.props.length;
+
+                let overlaps_parked_car = self
+                    .section_queues
+                    .get(&road_section)
+                    .is_some_and(|queue| {
+                        queue.iter().any(|&other_id| {
+                            other_id != car_id && {
+                                let other = self.car(other_id);
+                                next_position.overlaps(length, &other.position, other.props.length)
+                            }
+                        })
+                    });
+
+                let overlaps_already_moved_car = next_section_occupants
+                    .get(&road_section)
+                    .is_some_and(|occupants| {
+                        occupants.iter().any(|&(other_pos, other_length)| {
+                            CarPosition::positions_overlap(
+                                next_position.position_in_section,
+                                length,
+                                other_pos,
+                                other_length,
+                            )
+                        })
+                    });
+
+                if overlaps_parked_car || overlaps_already_moved_car {
+                    break 'next_pos old_position;
+                }
+
+                // car-following: also hold back if the car ahead -- further
+                // along this section, or (at an intersection) at the start
+                // of the section being entered -- is within
+                // min_following_gap cells, per the pre-move queue snapshot
+                if self
+                    .gap_to_leader(car_id, next_position)
+                    .is_some_and(|gap| gap < self.opts.min_following_gap)
                 {
+                    blocked_by_leader.insert(car_id, true);
                     break 'next_pos old_position;
                 }
 
@@ -381,13 +1132,41 @@ impl Grid {
                 break 'next_pos next_position;
             };
 
-            // add the car movement to the list
-            cars_to_move.insert(old_position, next_position);
-
-            let prev_car = next_positions.insert(next_position, car_id);
-            if let Some(prev_car_id) = prev_car {
-                panic!("{car_id:?} tried to move to {old_position:?} even though {prev_car_id:?} was already there");
+            // charging station slots are tracked by reserve()/release(), not
+            // by this map, since multiple cars at the same station compare
+            // equal as a CarPosition
+            if !next_position.is_at_charging_station() {
+                if let Some(&leading_car_id) = next_positions.get(&next_position) {
+                    // two cars' decisions this tick landed on the exact same
+                    // slot of the same RoadSection -- the overlap/gap checks
+                    // above should make this unreachable in practice, but
+                    // this is the last-resort net rather than a panic: block
+                    // the trailing car (the one processed second; the
+                    // leading car already claimed the slot) and flag the
+                    // collision for analytics/rendering instead of letting
+                    // it overlap.
+                    self.tick_events.push(TickEvent::Collision(
+                        car_id,
+                        leading_car_id,
+                        next_position,
+                    ));
+                    self.stats.collisions += 1;
+                    next_position = old_position;
+                } else {
+                    next_positions.insert(next_position, car_id);
+
+                    next_section_occupants
+                        .entry(next_position.road_section)
+                        .or_default()
+                        .push((
+                            next_position.position_in_section,
+                            self.car(car_id).props.length,
+                        ));
+                }
             }
+
+            // add the car movement to the list
+            cars_to_move.insert(car_id, next_position);
         }
 
         let mut cars_out_of_battery = vec![];
@@ -396,26 +1175,37 @@ impl Grid {
         for car in self.cars.values_mut() {
             car.ticks_since_out_of_battery = car.ticks_since_out_of_battery.saturating_add(1);
 
-            let Some(next_position) = cars_to_move.remove(&car.position) else {
+            let Some(next_position) = cars_to_move.remove(&car.id()) else {
                 panic!("{:?} was not in cars_to_move (no next position)", car.id());
             };
 
-            // if the car is at a charging station, charge its battery
+            car.blocked_by_leader = blocked_by_leader.remove(&car.id()).unwrap_or(false);
+
+            // if the car is at a charging station and done plugging in, charge
+            // its battery. connecting/disconnecting cars occupy the slot but
+            // don't draw any charge yet -- see ChargingStation::is_charging.
             if let Some(cs_id) = car.position.in_charging_station {
                 let cs = self.charging_stations.get(&cs_id).unwrap();
                 assert!(cs.cars.contains(&car.id()), "{:?} not in cs.cars", car.id());
 
-                car.battery.charging(cs);
+                if cs.is_charging(car.id()) {
+                    car.battery.charging(cs, &car.props.model);
+                }
                 car.active_action = None;
             }
 
             if car.position != next_position {
                 // car moves
 
-                // tick car battery
+                // tick car battery, scaled by the slope it's climbing/descending
                 if !car.props.agent.is_npc() {
-                    car.battery.discharge(car.props.discharge_rate);
-                    // car.battery.discharge(0.01);
+                    let slope = next_position.road_section.elevation()
+                        - car.position.road_section.elevation();
+                    let battery_before = car.battery.get();
+                    car.battery
+                        .discharge(car.props.discharge_rate, &car.props.model, slope);
+                    self.analytics
+                        .record_battery_consumed((battery_before - car.battery.get()).max(0.0));
                 }
 
                 if car.battery.is_empty()
@@ -432,32 +1222,46 @@ impl Grid {
                     // be punished and brought right back here next tick >:)
 
                     self.stats.out_of_battery += 1;
+                    self.analytics.record_out_of_battery();
                 } else {
                     let old_position = car.position;
 
                     // move the car
                     car.position = next_position;
-                    car.ticks_until_next_movement = car.props.speed;
+                    if next_position.road_section != old_position.road_section {
+                        self.analytics
+                            .record_section_entry(next_position.road_section);
+                    }
 
-                    // if the car entered/left charging station, tell the cs
+                    // faster road types let the car move again sooner, while a
+                    // congested section it's leaving behind slows it down
+                    let speed_limit_multiplier =
+                        next_position.road_section.road_type().speed_limit_multiplier;
+                    let congestion_speed_factor = self
+                        .section_congestion
+                        .get(&old_position.road_section)
+                        .map_or(1.0, |congestion| congestion.speed_factor);
+                    let combined_speed_factor = speed_limit_multiplier * congestion_speed_factor;
+
+                    car.ticks_until_next_movement = ((car.props.speed as f32
+                        / combined_speed_factor)
+                        .round() as usize)
+                        .max(1);
+
+                    // if the car entered/left charging station, tell the cs.
                     // note: we assume a car can't teleport from one cs to another
                     if old_position.in_charging_station.is_some()
                         && next_position.in_charging_station.is_some()
                     {
                         // car stays in same cs, do nothing
                     } else if let Some(cs_id) = old_position.in_charging_station {
+                        // release() also promotes the next queued car (if any)
+                        // into the freed slot
                         let cs = self.charging_stations.get_mut(&cs_id).unwrap();
-                        let car_index_in_cs = cs.cars.iter().position(|c| *c == car.id()).unwrap_or_else( ||
-                            panic!("car {:?} says it's in charging station, but charging station doesn't know about car",
-                                car.id())
-                        );
-
-                        cs.cars.swap_remove(car_index_in_cs);
-                    } else if let Some(cs_id) = next_position.in_charging_station {
-                        let cs = self.charging_stations.get_mut(&cs_id).unwrap();
-
-                        assert!(cs.has_space());
-                        cs.cars.push(car.id());
+                        cs.release(car.id());
+                    } else if next_position.in_charging_station.is_some() {
+                        // the active slot (or queue position) was already
+                        // granted by reserve() during the decision phase above
 
                         self.stats.enter_charging_stations += 1;
                     }
@@ -468,6 +1272,21 @@ impl Grid {
                 // "speed" ticks, or because there's something in front (traffic light
                 // or other car)
                 car.ticks_until_next_movement = car.ticks_until_next_movement.saturating_sub(1);
+
+                // idling in a jammed section still burns battery, same as a
+                // car stuck in real stop-and-go traffic
+                let is_jammed = self
+                    .section_congestion
+                    .get(&car.position.road_section)
+                    .is_some_and(|congestion| congestion.speed_factor < 1.0);
+                if is_jammed && !car.props.agent.is_npc() && !car.position.is_at_charging_station()
+                {
+                    let battery_before = car.battery.get();
+                    car.battery
+                        .discharge(Self::JAM_IDLE_DISCHARGE_RATE, &car.props.model, 0.0);
+                    self.analytics
+                        .record_battery_consumed((battery_before - car.battery.get()).max(0.0));
+                }
             }
 
             // assert_ne!(car.position, next_position);
@@ -502,7 +1321,7 @@ impl Grid {
         // spawn cars waiting to be spawned
         if !self.cars_to_spawn.is_empty() {
             let cars_to_spawn = std::mem::take(&mut self.cars_to_spawn);
-            let mut rng = rand::thread_rng();
+            let mut rng = mem::replace(&mut self.car_rng, StdRng::from_entropy());
 
             for mut car_to_spawn in cars_to_spawn {
                 if let Some((out_of_battery_position, passengers)) = car_to_spawn.out_of_battery {
@@ -536,7 +1355,19 @@ impl Grid {
                     continue;
                 }
 
-                let pos_is_taken = |pos: &_| self.car_positions.contains_key(pos);
+                // span-aware, so a long vehicle doesn't spawn straddling a
+                // car that's already there even though their front cells differ
+                let length = car_to_spawn.props.length;
+                let pos_is_taken = |pos: &CarPosition| {
+                    if pos.is_at_charging_station() {
+                        return self.car_positions.contains_key(pos);
+                    }
+
+                    self.car_positions.iter().any(|(&other_pos, &other_id)| {
+                        other_pos.road_section == pos.road_section
+                            && pos.overlaps(length, &other_pos, self.car(other_id).props.length)
+                    })
+                };
                 let car_position = car_to_spawn.position(&mut rng, pos_is_taken);
                 let initial_battery = match self.opts.discharge_rate == 0.0 {
                     true => 1.0, // if battery never discharges, always have full battery
@@ -550,6 +1381,7 @@ impl Grid {
             }
 
             // self.cars.shrink_to_fit();
+            self.car_rng = rng;
         }
 
         // check we didn't lose any cars in the process
@@ -580,8 +1412,8 @@ impl Grid {
         let car = self.cars.get_mut(&car_id).unwrap();
         car.passengers.retain_mut(|p| {
             match p {
-                CarPassenger::PickingUp(_) => false, // discard picking up
-                CarPassenger::DroppingOff(p) => {
+                CarPassenger::PickingUp(..) => false, // discard picking up
+                CarPassenger::DroppingOff(p, _) => {
                     p.car_on_its_way = false; // all pickup commands get reset between ticks
                     true
                 }
@@ -600,11 +1432,16 @@ impl Grid {
             );
         }
 
-        // if it's being spawned in a charging station,
-        // tell the cs it has a car now
+        // if it's being spawned in a charging station, tell the cs it has a
+        // car now. this bypasses ChargingStation::reserve (the car is placed
+        // directly rather than queued), so it needs its own connect
+        // countdown -- an out-of-battery car doesn't get to skip plugging in
+        // just because it teleported onto the slot.
         if let Some(cs_id) = car.position.in_charging_station {
             let cs = self.charging_stations.get_mut(&cs_id).unwrap();
             cs.cars.push(car.id());
+            cs.connecting
+                .insert(car.id(), ChargingStation::TIME_TO_CONNECT);
         }
 
         let dupe_car = self.cars.insert(car.id(), car);
@@ -616,13 +1453,89 @@ impl Grid {
         }
     }
 
+    // drains every car departure/passenger arrival due this tick from
+    // self.scenario (if one is set). a departure whose requested cell is
+    // occupied is handed back to the scenario for a bounded blind retry a
+    // few ticks later, instead of CarToSpawn::position's random-fallback-
+    // then-panic behaviour.
+    fn tick_scenario(&mut self) {
+        let Some(mut scenario) = self.scenario.take() else {
+            return;
+        };
+
+        let current_tick = self.ticks_passed;
+
+        for (departure, retries_left) in scenario.due_cars(current_tick) {
+            let length = departure.props.length;
+            let taken = departure.position.is_some_and(|pos| {
+                if pos.is_at_charging_station() {
+                    self.car_positions.contains_key(&pos)
+                } else {
+                    self.car_positions.iter().any(|(&other_pos, &other_id)| {
+                        other_pos.road_section == pos.road_section
+                            && pos.overlaps(length, &other_pos, self.car(other_id).props.length)
+                    })
+                }
+            });
+
+            if taken {
+                scenario.retry_car(departure, retries_left, current_tick);
+            } else {
+                self.add_car(departure.props, departure.position);
+            }
+        }
+
+        for (arrival, retries_left) in scenario.due_passengers(current_tick) {
+            let taken = self.waiting_passenger_positions.contains_key(&arrival.start)
+                || self.charging_station_entrance_at(arrival.start).is_some();
+
+            if taken {
+                scenario.retry_passenger(arrival, retries_left, current_tick);
+            } else {
+                let passenger = passenger_from_arrival(&arrival, current_tick);
+
+                let event = TickEvent::PassengerSpawned(passenger.id);
+                self.tick_events.push(event);
+
+                self.waiting_passenger_positions
+                    .insert(passenger.start, passenger.id);
+                self.waiting_passengers.insert(passenger.id, passenger);
+                self.stats.passenger_spawns += 1;
+            }
+        }
+
+        self.scenario = Some(scenario);
+    }
+
     fn tick_passengers(&mut self) {
+        // give up on any waiting passenger who's been waiting longer than
+        // GridOpts::passenger_patience_ticks -- A/B Street-style trip
+        // cancellation, so an RL agent gets a negative-reward signal for
+        // starving demand instead of passengers waiting forever. a car
+        // already en route to one of these (CarPassenger::PickingUp) just
+        // finds it gone below, the same as if another car had beaten it
+        // there.
+        let abandoned: Vec<PassengerId> = self
+            .waiting_passengers
+            .values()
+            .filter(|p| self.ticks_passed - p.start_tick > self.opts.passenger_patience_ticks)
+            .map(|p| p.id)
+            .collect();
+
+        for passenger_id in abandoned {
+            let passenger = self.waiting_passengers.remove(&passenger_id).unwrap();
+            self.waiting_passenger_positions.remove(&passenger.start);
+            self.tick_events
+                .push(TickEvent::PassengerAbandoned(passenger_id));
+            self.stats.passenger_abandonments += 1;
+        }
+
         // spawn passengers
         let passenger_spawn_rate_this_tick = self
             .current_passenger_event()
             .and_then(|e| e.spawn_rate)
             .unwrap_or(self.opts.passenger_spawn_rate);
-        let mut rng = rand::thread_rng();
+        let mut rng = mem::replace(&mut self.passenger_rng, StdRng::from_entropy());
         while self.waiting_passengers.len() < self.opts.max_passengers
             && rng.gen::<f32>() < passenger_spawn_rate_this_tick
         {
@@ -637,32 +1550,47 @@ impl Grid {
 
             self.stats.passenger_spawns += 1;
         }
+        self.passenger_rng = rng;
 
         // pick up & drop off passengers
         for car in self.cars.values_mut() {
-            let old_passengers = mem::take(&mut car.passengers);
+            let mut old_passengers = mem::take(&mut car.passengers);
+            // consult the planned order (see Route::cheapest_insertion):
+            // when more than one onboard passenger is up for consideration
+            // this tick, the one planned to alight earliest is handled
+            // first, rather than whatever order they happen to sit in the
+            // vec.
+            old_passengers.sort_by_key(|p| p.dropoff_order());
 
             for passenger in old_passengers {
                 match passenger {
-                    CarPassenger::DroppingOff(passenger) => {
+                    CarPassenger::DroppingOff(passenger, dropoff_order) => {
                         // === drop off passenger ===
                         let drop_off_here = passenger.destination == car.position;
                         if drop_off_here {
+                            let trip_ticks = self.ticks_passed - passenger.start_tick;
                             // print!("Car dropped off passenger! ");
                             let event = TickEvent::PassengerDroppedOff(car.props.id, passenger);
                             self.tick_events.push(event);
                             self.stats.passenger_dropoffs += 1;
+                            self.analytics.record_dropoff(trip_ticks);
                             car.active_action = None;
                         } else {
                             // if we don't drop the passenger off, we keep them
-                            car.passengers.push(CarPassenger::DroppingOff(passenger));
+                            car.passengers
+                                .push(CarPassenger::DroppingOff(passenger, dropoff_order));
                         }
                     }
 
-                    CarPassenger::PickingUp(passenger_id) => {
+                    CarPassenger::PickingUp(passenger_id, dropoff_order) => {
                         let passenger = self.waiting_passengers.get(&passenger_id);
                         let Some(passenger) = passenger else {
-                            // this passenger just got picked up by another car
+                            // this passenger just got picked up by another
+                            // car, or abandoned (see the patience check
+                            // above) -- either way, the car's current
+                            // command is stale, so it isn't left stuck
+                            // showing an action it can no longer complete.
+                            car.active_action = None;
                             continue;
                         };
 
@@ -676,9 +1604,12 @@ impl Grid {
                             // create the event while we still own the passenger variable
                             let event = TickEvent::PassengerPickedUp(car.props.id, passenger.id);
                             self.tick_events.push(event);
+                            self.analytics
+                                .record_pickup(self.ticks_passed - passenger.start_tick);
 
-                            // and finally put them into the car
-                            let car_passenger = CarPassenger::DroppingOff(passenger);
+                            // and finally put them into the car, carrying
+                            // over the planned drop-off order
+                            let car_passenger = CarPassenger::DroppingOff(passenger, dropoff_order);
                             car.passengers.push(car_passenger);
 
                             // print!("Car picked up passenger! ");
@@ -692,6 +1623,73 @@ impl Grid {
         }
     }
 
+    // advances every train one tick, then boards/alights passengers at any
+    // train that's currently dwelling at a station.
+    fn tick_trains(&mut self) {
+        let train_ids: Vec<TrainId> = self.trains.keys().copied().collect();
+
+        for train_id in train_ids {
+            let line_id = self.trains[&train_id].line;
+
+            let line = &self.rail_lines[&line_id];
+            self.trains.get_mut(&train_id).unwrap().tick(line);
+
+            let Some(stop_index) = self.trains[&train_id].at_stop() else {
+                continue;
+            };
+            let station = self.rail_lines[&line_id].stops[stop_index];
+
+            self.board_and_alight_train(train_id, station);
+        }
+    }
+
+    // passengers alight the moment their destination matches the station a
+    // train is dwelling at, and waiting passengers starting exactly there
+    // board immediately, space permitting -- simpler than the two-phase
+    // CarPassenger::PickingUp/DroppingOff dance cars use, since a train
+    // doesn't chase a passenger down, it just waits for them at its stops.
+    fn board_and_alight_train(&mut self, train_id: TrainId, station: RoadSection) {
+        let boarded = mem::take(&mut self.trains.get_mut(&train_id).unwrap().passengers);
+        let (alighting, staying): (Vec<_>, Vec<_>) = boarded
+            .into_iter()
+            .partition(|p| p.destination.road_section == station);
+        self.trains.get_mut(&train_id).unwrap().passengers = staying;
+        self.stats.passenger_dropoffs += alighting.len();
+        for passenger in alighting {
+            self.analytics
+                .record_dropoff(self.ticks_passed - passenger.start_tick);
+            self.tick_events
+                .push(TickEvent::TransitAlighted(train_id, passenger));
+        }
+
+        loop {
+            let train = &self.trains[&train_id];
+            if train.passengers.len() >= train.capacity {
+                break;
+            }
+
+            let boardable = self
+                .unassigned_passengers()
+                .into_iter()
+                .find(|p| p.start.road_section == station)
+                .map(|p| p.id);
+
+            let Some(passenger_id) = boardable else {
+                break;
+            };
+
+            let passenger = self.waiting_passengers.remove(&passenger_id).unwrap();
+            self.waiting_passenger_positions.remove(&passenger.start);
+            self.stats.passenger_pickups += 1;
+            self.analytics
+                .record_pickup(self.ticks_passed - passenger.start_tick);
+            self.tick_events
+                .push(TickEvent::TransitBoarded(train_id, passenger.id));
+
+            self.trains.get_mut(&train_id).unwrap().passengers.push(passenger);
+        }
+    }
+
     fn send_transition_result(&self, new_state: PyGridState) {
         for car in self.cars() {
             let Some(py_agent) = car.props.agent.as_py_agent() else {
@@ -706,7 +1704,7 @@ impl Grid {
     fn random_empty_car_position(&self, mut rng: impl Rng) -> CarPosition {
         for _ in 0..1000 {
             let position = CarPosition::random(&mut rng);
-            if !self.has_car_at(&position) {
+            if !self.has_car_at(&position) && !self.closed_sections.contains(&position.road_section) {
                 return position;
             }
         }
@@ -714,9 +1712,93 @@ impl Grid {
         panic!("Grid is full!")
     }
 
-    fn is_red_traffic_light(&self, car_pos: &CarPosition) -> bool {
-        return car_pos.is_at_intersection()
-            && self.traffic_lights[&car_pos.road_section].state == LightState::Red;
+    // whether a car sitting at `car_pos` must wait before taking `decision`,
+    // per the IntersectionControl for the corner it's about to enter (a red/
+    // forbidden signal phase, a stop sign it hasn't got priority at, or a
+    // denied Reservation).
+    fn movement_blocked(
+        &mut self,
+        car_id: CarId,
+        car_pos: &CarPosition,
+        decision: CarDecision,
+    ) -> bool {
+        if !car_pos.is_at_intersection() || decision == CarDecision::ChargeBattery {
+            return false;
+        }
+
+        let is_reservation = matches!(
+            self.intersection_control_at(&car_pos.road_section),
+            IntersectionControl::Reservation(_)
+        );
+
+        if is_reservation {
+            let intersection_id = IntersectionId::entering(car_pos.road_section);
+            return !self.reservation_allows(intersection_id, car_id, *car_pos, decision);
+        }
+
+        let control = self.intersection_control_at(&car_pos.road_section);
+        !control.allows(car_pos.road_section.direction, decision)
+    }
+
+    // grants or denies `car_id`'s requested `decision` under an
+    // IntersectionControl::Reservation, against whatever this intersection
+    // has already accepted this tick (see intersection_reservations and
+    // IntersectionReservationPolicy's doc comment). also fires the
+    // corresponding TickEvent.
+    fn reservation_allows(
+        &mut self,
+        intersection_id: IntersectionId,
+        car_id: CarId,
+        car_pos: CarPosition,
+        decision: CarDecision,
+    ) -> bool {
+        let dont_block_the_box = match self.intersection_control_at(&car_pos.road_section) {
+            IntersectionControl::Reservation(policy) => policy.dont_block_the_box,
+            _ => unreachable!("reservation_allows only called when the control is Reservation"),
+        };
+
+        if dont_block_the_box {
+            let target = car_pos.take_decision(decision);
+            let blocked = self.queue_length(&target.road_section)
+                >= target.road_section.direction.section_capacity();
+            if blocked {
+                self.tick_events
+                    .push(TickEvent::IntersectionReservationDenied(
+                        car_id,
+                        intersection_id,
+                    ));
+                return false;
+            }
+        }
+
+        let incoming = car_pos.road_section.direction;
+        let accepted = self
+            .intersection_reservations
+            .entry(intersection_id)
+            .or_default();
+        let conflicts = accepted.iter().any(|&(_, other_incoming, other_decision)| {
+            IntersectionReservationPolicy::movements_conflict(
+                (incoming, decision),
+                (other_incoming, other_decision),
+            )
+        });
+
+        if conflicts {
+            self.tick_events
+                .push(TickEvent::IntersectionReservationDenied(
+                    car_id,
+                    intersection_id,
+                ));
+            return false;
+        }
+
+        accepted.push((car_id, incoming, decision));
+        self.tick_events
+            .push(TickEvent::IntersectionReservationGranted(
+                car_id,
+                intersection_id,
+            ));
+        true
     }
 
     pub fn waiting_passengers(&self) -> impl Iterator<Item = &Passenger> {
@@ -743,13 +1825,98 @@ impl Grid {
             panic!("Car already has {} passengers", car.passengers.len());
         }
 
-        car.passengers.push(CarPassenger::PickingUp(passenger_id));
+        // no route plan behind this call (unlike assign_car_to_passenger_pooled),
+        // so this passenger is simply last to alight of whoever's currently onboard
+        let dropoff_order = car.passengers.len();
+        car.passengers
+            .push(CarPassenger::PickingUp(passenger_id, dropoff_order));
+    }
+
+    // reverses assign_car_to_passenger/assign_car_to_passenger_pooled: drops
+    // car_id's dangling CarPassenger::PickingUp(passenger_id) entry, if it
+    // still has one (a car that already reached the passenger and moved it
+    // to DroppingOff is untouched), and clears the passenger's
+    // car_on_its_way flag. lets an external dispatcher revoke a stale
+    // assignment -- e.g. the assigned car is taking too long, or got
+    // reassigned elsewhere -- without waiting for car_remove_pick_up_commands'
+    // normal per-tick reset.
+    pub fn unassign_car_from_passenger(&mut self, car_id: CarId, passenger_id: PassengerId) {
+        self.car_mut(car_id)
+            .passengers
+            .retain(|p| !matches!(p, CarPassenger::PickingUp(id, _) if *id == passenger_id));
+
+        if let Some(passenger) = self.waiting_passengers.get_mut(&passenger_id) {
+            passenger.car_on_its_way = false;
+        }
+    }
+
+    // assign_car_to_passenger's ride-pooling counterpart: instead of always
+    // committing the assignment, run the same cheapest-insertion heuristic
+    // Dispatcher::plan uses across a whole fleet (see car_agent::Route) for
+    // just this one car, and only commit if some insertion keeps the car's
+    // onboard count within opts.passengers_per_car at every point between
+    // the pickup and dropoff. returns the winning insertion's marginal
+    // Manhattan-distance cost so an external dispatcher juggling several
+    // candidate cars for the same passenger can compare them before
+    // picking one -- or None, leaving the car and passenger untouched, if
+    // no insertion is feasible.
+    //
+    // this reuses Route/cheapest_insertion rather than keeping a second,
+    // persistent stop-order list on Car: the existing CentralDispatch path
+    // already re-derives a car's planned order from car.passengers every
+    // tick (see Route::from_car), and a car's own CarPathAgent (e.g.
+    // NearestPassenger::waypoints_for) is what actually turns that order
+    // into a driven path, so a second standing plan on Car would just be
+    // another thing to keep in sync with those rather than new behaviour.
+    pub fn assign_car_to_passenger_pooled(
+        &mut self,
+        car_id: CarId,
+        passenger: PassengerId,
+    ) -> Option<Detour> {
+        let passenger = self
+            .waiting_passengers
+            .get(&passenger)
+            .expect("Car tried to assign to non-existent passenger");
+        let (start, destination) = (passenger.start, passenger.destination);
+        let passenger_id = passenger.id;
+
+        let max_onboard = self.opts.passengers_per_car;
+        let route = Route::from_car(self.car(car_id));
+        let (pickup_idx, dropoff_idx, added_cost) =
+            route.cheapest_insertion(start, destination, max_onboard)?;
+
+        // car.passengers only holds DroppingOff entries right now (see
+        // Route::from_car), so pickup_idx indexes directly into it --
+        // insert at the planned position rather than appending, and carry
+        // dropoff_idx along as this passenger's planned alighting order
+        // (consulted once picked up, see Grid::tick_passengers)
+        self.car_mut(car_id)
+            .passengers
+            .insert(pickup_idx, CarPassenger::PickingUp(passenger_id, dropoff_idx));
+
+        Some(Detour {
+            pickup_idx,
+            dropoff_idx,
+            added_cost,
+        })
     }
 
     pub fn get_idle_passenger(&self, passenger: PassengerId) -> Option<&Passenger> {
         self.waiting_passengers.get(&passenger)
     }
 
+    pub fn rail_lines(&self) -> impl Iterator<Item = &RailLine> {
+        self.rail_lines.values()
+    }
+
+    pub fn trains(&self) -> impl Iterator<Item = &Train> {
+        self.trains.values()
+    }
+
+    pub fn rail_line(&self, id: RailLineId) -> &RailLine {
+        &self.rail_lines[&id]
+    }
+
     pub fn py_state(&self, pov_car_id: CarId) -> PyGridState {
         let pov_car = self.car(pov_car_id);
         self.tick_state
@@ -4,6 +4,7 @@ use std::{io::Write, mem};
 use macroquad::color::*;
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     logic::car::NextCarPosition,
@@ -12,14 +13,16 @@ use crate::{
 
 use super::{
     car::{Car, CarDecision, CarId, CarPassenger, CarPosition, CarProps, CarToSpawn},
-    car_agent::{NullAgent, PythonAgent, RandomTurns},
+    car_agent::{NullAgent, PythonAgent, RandomTurns, ShuttleRoute},
     ev::{ChargingStation, ChargingStationId},
     grid::Grid,
+    intersection::IntersectionId,
     passenger::{Passenger, PassengerId},
-    util::{hashmap_with_capacity, Direction, HashMap, HashSet, Orientation, RoadSection},
+    rail::TrainId,
+    util::{hashmap_with_capacity, Direction, DrivingSide, HashMap, HashSet, Orientation, RoadSection},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[pyclass]
 pub enum LightState {
     Red,
@@ -42,7 +45,7 @@ impl LightState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[pyclass]
 pub struct TrafficLight {
     #[pyo3(get)]
@@ -89,21 +92,103 @@ impl TrafficLight {
     }
 }
 
+// A/B Street-style fundamental diagram: free flow up to a threshold
+// occupancy, then speed decays linearly down to a floor as the section
+// fills up. Recomputed every tick from `Grid::car_positions` and kept
+// around so both the sim (car speed) and the renderer (road tinting) can
+// read the same numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[pyclass]
+pub struct SectionCongestion {
+    #[pyo3(get)]
+    pub occupancy: f32,
+    #[pyo3(get)]
+    pub speed_factor: f32,
+}
+
+impl SectionCongestion {
+    // below this occupancy, traffic flows at the road's full speed
+    const FREE_FLOW_OCCUPANCY: f32 = 0.5;
+    // speed factor once a section is at (or beyond) full occupancy
+    const JAM_SPEED_FACTOR: f32 = 0.15;
+
+    pub const FREE_FLOW: Self = Self {
+        occupancy: 0.0,
+        speed_factor: 1.0,
+    };
+
+    pub fn from_occupancy(occupancy: f32) -> Self {
+        let speed_factor = if occupancy <= Self::FREE_FLOW_OCCUPANCY {
+            1.0
+        } else {
+            let jam_progress =
+                (occupancy - Self::FREE_FLOW_OCCUPANCY) / (1.0 - Self::FREE_FLOW_OCCUPANCY);
+            1.0 - jam_progress.min(1.0) * (1.0 - Self::JAM_SPEED_FACTOR)
+        };
+
+        Self {
+            occupancy,
+            speed_factor,
+        }
+    }
+}
+
+impl Default for SectionCongestion {
+    fn default() -> Self {
+        Self::FREE_FLOW
+    }
+}
+
 pub enum TickEvent {
     PassengerSpawned(PassengerId),
     PassengerPickedUp(CarId, PassengerId),
     PassengerDroppedOff(CarId, Passenger),
     CarOutOfBattery(CarId, CarPosition),
+    // two cars' decisions this tick put them in the same slot of the same
+    // RoadSection: (trailing car, leading car, the contested position).
+    // the trailing car is blocked rather than allowed to overlap -- see
+    // Grid::tick_cars -- this just records that it happened.
+    Collision(CarId, CarId, CarPosition),
+    // the rail/transit equivalent of PassengerPickedUp/PassengerDroppedOff --
+    // see Grid::board_and_alight_train. fired for a Train rather than a Car,
+    // since trains board/alight immediately rather than going through
+    // CarPassenger::PickingUp/DroppingOff.
+    TransitBoarded(TrainId, PassengerId),
+    TransitAlighted(TrainId, Passenger),
+    // a car joined a charging station's FIFO queue because its slots were
+    // all full -- see ChargingStation::reserve/queue.
+    CarQueuedForCharging(CarId, ChargingStationId),
+    // a car's connect countdown finished and it started actually drawing
+    // charge -- see ChargingStation::tick/connecting.
+    CarStartedCharging(CarId, ChargingStationId),
+    // a section was closed/reopened to traffic -- see
+    // Grid::close_section/open_section.
+    SectionClosed(RoadSection),
+    SectionReopened(RoadSection),
+    // a waiting passenger's start section just closed, so no car will ever
+    // be able to pick them up there -- see Grid::close_section. this is a
+    // cheap, necessary-but-not-sufficient check (only catches the start
+    // section itself closing, not the start becoming unreachable via some
+    // other closure elsewhere in the grid), not a full reachability search.
+    PassengerStartUnreachable(PassengerId),
+    // a car's requested movement was granted/denied under
+    // IntersectionControl::Reservation -- see Grid::reservation_allows.
+    IntersectionReservationGranted(CarId, IntersectionId),
+    IntersectionReservationDenied(CarId, IntersectionId),
+    // a waiting passenger gave up after waiting longer than
+    // GridOpts::passenger_patience_ticks -- see Grid::tick_passengers.
+    PassengerAbandoned(PassengerId),
 }
 
 #[pyclass]
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GridStats {
     pub ticks: usize,
 
     pub passenger_spawns: usize,
     pub passenger_pickups: usize,
     pub passenger_dropoffs: usize,
+    pub passenger_abandonments: usize,
 
     pub pick_up_requests: usize,
     pub drop_off_requests: usize,
@@ -112,6 +197,7 @@ pub struct GridStats {
 
     pub enter_charging_stations: usize,
     pub out_of_battery: usize,
+    pub collisions: usize,
 
     pub ticks_with_n_passengers: Vec<usize>,
     pub ticks_picking_up_n_closest_passenger: Vec<usize>,
@@ -129,12 +215,14 @@ impl GridStats {
             "passenger_spawns",
             "passenger_pickups",
             "passenger_dropoffs",
+            "passenger_abandonments",
             "pick_up_requests",
             "drop_off_requests",
             "charge_requests",
             "head_towards_requests",
             "enter_charging_stations",
             "out_of_battery",
+            "collisions",
         ];
         let mut headers = headers.iter().map(|s| s.to_string()).collect::<Vec<_>>();
 
@@ -157,12 +245,14 @@ impl GridStats {
             self.passenger_spawns.to_string(),
             self.passenger_pickups.to_string(),
             self.passenger_dropoffs.to_string(),
+            self.passenger_abandonments.to_string(),
             self.pick_up_requests.to_string(),
             self.drop_off_requests.to_string(),
             self.charge_requests.to_string(),
             self.head_towards_requests.to_string(),
             self.enter_charging_stations.to_string(),
             self.out_of_battery.to_string(),
+            self.collisions.to_string(),
         ];
 
         for n in 0..=Self::MAX_PASSENGERS_PER_CAR {
@@ -272,7 +362,7 @@ fn test_wrap_negative_coords() {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[pyclass]
 pub struct GridOpts {
     #[pyo3(get)]
@@ -285,6 +375,12 @@ pub struct GridOpts {
     pub agent_car_count: u32,
     #[pyo3(get)]
     pub npc_car_count: u32,
+    // how many cars Grid::new spawns under one shared CentralDispatch
+    // fleet controller (see car_agent::CentralDispatch), a cheapest-
+    // insertion VRP baseline to benchmark the Python RL agents against.
+    // 0 keeps today's behavior of never spawning any.
+    #[pyo3(get)]
+    pub central_dispatch_car_count: u32,
     #[pyo3(get)]
     pub passengers_per_car: usize,
     #[pyo3(get)]
@@ -303,6 +399,87 @@ pub struct GridOpts {
     pub deterministic_mode: bool,
     #[pyo3(get)]
     pub verbose: bool,
+    // >= 1.0: multiplies the A* heuristic used by NPC agents (RandomDestination,
+    // NearestPassenger), trading exactness for fewer node expansions. 1.0 keeps
+    // today's optimal behavior; RL/evaluation paths always route exactly regardless
+    // of this setting, since they go through Car::find_path/CarPosition::path_to.
+    #[pyo3(get)]
+    pub npc_heuristic_weight: f64,
+    // when true, NPC agents (RandomDestination, NearestPassenger) route
+    // through Grid::section_congestion, paying a BPR-style cost penalty for
+    // occupied sections so they route around jams instead of through them.
+    #[pyo3(get)]
+    pub route_around_congestion: bool,
+    // caps the open set NPC pathfinding (RandomDestination, NearestPassenger)
+    // keeps around to the best `k` nodes, via Path::find_weighted's
+    // beam_search fallback; usize::MAX keeps today's exact, unbounded astar.
+    // a small k bounds pathfinding memory/time on very large grids at the
+    // risk of occasionally failing to find a path, which falls back to the
+    // same exact astar. RL/evaluation paths are unaffected, since they go
+    // through Car::find_path/CarPosition::path_to.
+    #[pyo3(get)]
+    pub npc_beam_width: usize,
+    // A/B Street-style car-following: a car may only advance into a cell (be
+    // it the next cell in its own section or the first cell of the next
+    // section at an intersection) if the rear of the car ahead in that
+    // section is at least this many cells further along (see
+    // Grid::gap_to_leader), i.e. at least min_following_gap - 1 empty cells
+    // between them, per Grid::section_queues. 1 keeps today's "just don't overlap" behavior;
+    // a larger gap spaces cars out more realistically at the cost of
+    // slower throughput.
+    #[pyo3(get)]
+    pub min_following_gap: usize,
+    // when true, Grid::new precomputes an all-pairs section distance table
+    // (see pathfinding::SectionDistances) and Grid::section_distance looks
+    // distances up in it instead of calling Path::find; used by
+    // PyPassenger::idle/riding_at to report distance_to_destination without
+    // running A* per passenger per tick build. false keeps today's lazy
+    // Path::find behavior -- cheaper to start up, more expensive per lookup.
+    #[pyo3(get)]
+    pub precompute_section_distances: bool,
+    // when Some(r), with_pov additionally groups idle passengers into
+    // PyPassengerCluster entries via single-link agglomerative clustering
+    // (any two passengers within `r` road distance of each other end up in
+    // the same cluster) and exposes them as idle_passenger_clusters,
+    // alongside the existing flat idle_passengers list. None skips the
+    // clustering pass entirely -- see py_grid::cluster_idle_passengers.
+    #[pyo3(get)]
+    pub cluster_radius: Option<usize>,
+    // master RNG seed: Grid::new derives Grid::passenger_rng and
+    // Grid::car_rng from it, so passenger placement, respawn slots and
+    // spawn-rate rolls are a pure function of (seed, agent decisions) and
+    // an episode can be replayed exactly by reusing it. doesn't cover
+    // every random call site in the codebase -- see Grid::scenario_seed's
+    // doc comment for the ones left as unseeded rand::thread_rng()
+    // (landmark selection, charging station entrance pick).
+    #[pyo3(get)]
+    pub seed: u64,
+    // width, in ticks, of each window Grid::analytics's time series groups
+    // section entries and passenger wait/trip ticks into -- see
+    // analytics::TimeSeries. unlike Analytics's bounded rolling window
+    // (samples, evicted past MAX_WINDOW_TICKS), the time series keeps one
+    // bucket per window for the whole episode, so Python can plot
+    // congestion hotspots and wait-time distributions over an episode
+    // instead of only a recent snapshot or an end-of-run total.
+    #[pyo3(get)]
+    pub time_series_bucket_ticks: usize,
+    // scheduled-transit baselines, à la A/B Street's TransitSimState: one
+    // FixedRoute shuttle car is seeded per route in Grid::new, cycling its
+    // stops forever and competing with the taxi/agent cars for the same
+    // passenger pool -- see car_agent::FixedRoute.
+    #[pyo3(get)]
+    pub shuttle_routes: Vec<ShuttleRoute>,
+    // a waiting passenger who's gone this many ticks without being picked up
+    // gives up -- see Grid::tick_passengers' abandonment pass and
+    // TickEvent::PassengerAbandoned. usize::MAX keeps today's "wait forever"
+    // behavior.
+    #[pyo3(get)]
+    pub passenger_patience_ticks: usize,
+    // which side of the road this scenario's traffic drives on -- see
+    // util::DrivingSide. Grid::new sets DrivingSide::current() from this, so
+    // the same trained agent can be evaluated under either convention.
+    #[pyo3(get)]
+    pub driving_side: DrivingSide,
 }
 
 #[pymethods]
@@ -314,6 +491,7 @@ impl GridOpts {
         max_passengers: usize,
         agent_car_count: u32,
         npc_car_count: u32,
+        central_dispatch_car_count: u32,
         passengers_per_car: usize,
         charging_stations: Vec<CarPosition>,
         charging_station_capacity: usize,
@@ -323,13 +501,30 @@ impl GridOpts {
         passenger_events: Vec<PassengerEvent>,
         deterministic_mode: bool,
         verbose: bool,
+        npc_heuristic_weight: f64,
+        route_around_congestion: bool,
+        npc_beam_width: usize,
+        min_following_gap: usize,
+        precompute_section_distances: bool,
+        cluster_radius: Option<usize>,
+        seed: u64,
+        time_series_bucket_ticks: usize,
+        shuttle_routes: Vec<ShuttleRoute>,
+        passenger_patience_ticks: usize,
+        driving_side: DrivingSide,
     ) -> Self {
+        assert!(npc_heuristic_weight >= 1.0);
+        assert!(npc_beam_width >= 1);
+        assert!(min_following_gap >= 1);
+        assert!(time_series_bucket_ticks >= 1);
+
         Self {
             initial_passenger_count,
             passenger_spawn_rate,
             max_passengers,
             agent_car_count,
             npc_car_count,
+            central_dispatch_car_count,
             passengers_per_car,
             charging_stations,
             charging_station_capacity,
@@ -339,6 +534,17 @@ impl GridOpts {
             passenger_events,
             deterministic_mode,
             verbose,
+            npc_heuristic_weight,
+            route_around_congestion,
+            npc_beam_width,
+            min_following_gap,
+            precompute_section_distances,
+            cluster_radius,
+            seed,
+            time_series_bucket_ticks,
+            shuttle_routes,
+            passenger_patience_ticks,
+            driving_side,
         }
     }
 }
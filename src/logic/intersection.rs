@@ -0,0 +1,260 @@
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    car::CarDecision,
+    util::{Direction, HashSet, RoadSection},
+};
+
+// identifies one intersection by the grid coordinates of the two roads
+// crossing there, independent of which of the (up to 4) RoadSections
+// approaching it a car happens to be on -- all four approaches share one
+// IntersectionControl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[pyclass]
+pub struct IntersectionId {
+    #[pyo3(get)]
+    pub vertical_road: isize,
+    #[pyo3(get)]
+    pub horizontal_road: isize,
+}
+
+impl IntersectionId {
+    // the intersection `section` leads into, i.e. the corner a car on
+    // `section` is about to decide at. same offset arithmetic as
+    // RoadSection::turn, just stopping short of picking a decision.
+    pub fn entering(section: RoadSection) -> Self {
+        let far_index = section.section_index + section.direction.towards_positive() as isize;
+
+        match section.direction.is_horizontal() {
+            true => Self {
+                vertical_road: far_index,
+                horizontal_road: section.road_index,
+            },
+            false => Self {
+                vertical_road: section.road_index,
+                horizontal_road: far_index,
+            },
+        }
+    }
+}
+
+// one portion of a traffic signal's cycle: which (incoming direction,
+// decision) movements get a green light, and how long it holds before the
+// signal advances to the next phase.
+#[derive(Debug, Clone)]
+pub struct SignalPhase {
+    pub movements: HashSet<(Direction, CarDecision)>,
+    pub duration_ticks: usize,
+}
+
+impl SignalPhase {
+    fn allows(&self, incoming: Direction, decision: CarDecision) -> bool {
+        self.movements.contains(&(incoming, decision))
+    }
+
+    fn all_movements_from(directions: [Direction; 2], duration_ticks: usize) -> Self {
+        let movements = directions
+            .into_iter()
+            .flat_map(|d| {
+                [
+                    CarDecision::GoStraight,
+                    CarDecision::TurnLeft,
+                    CarDecision::TurnRight,
+                ]
+                .into_iter()
+                .map(move |decision| (d, decision))
+            })
+            .collect();
+
+        Self {
+            movements,
+            duration_ticks,
+        }
+    }
+}
+
+// mirrors A/B Street's ControlTrafficSignal: an ordered cycle of phases,
+// with a cursor (current_phase/ticks_left) advanced once per tick by
+// Grid::tick_intersection_controls.
+#[derive(Debug, Clone)]
+pub struct ControlTrafficSignal {
+    phases: Vec<SignalPhase>,
+    current_phase: usize,
+    ticks_left: usize,
+}
+
+impl ControlTrafficSignal {
+    // a sensible default plan: two phases splitting the horizontal
+    // through-road from the vertical one, the same split the old
+    // orientation-based TrafficLight used, just expressed as explicit
+    // per-movement permissions.
+    pub fn default_plan(phase_ticks: usize) -> Self {
+        let phases = vec![
+            SignalPhase::all_movements_from([Direction::Left, Direction::Right], phase_ticks),
+            SignalPhase::all_movements_from([Direction::Up, Direction::Down], phase_ticks),
+        ];
+
+        Self {
+            current_phase: 0,
+            ticks_left: phases[0].duration_ticks,
+            phases,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if self.ticks_left > 0 {
+            self.ticks_left -= 1;
+            return;
+        }
+
+        self.current_phase = (self.current_phase + 1) % self.phases.len();
+        self.ticks_left = self.phases[self.current_phase].duration_ticks;
+    }
+
+    pub fn current_phase(&self) -> &SignalPhase {
+        &self.phases[self.current_phase]
+    }
+
+    fn allows(&self, incoming: Direction, decision: CarDecision) -> bool {
+        self.current_phase().allows(incoming, decision)
+    }
+}
+
+// mirrors A/B Street's ControlStopSign: approaches in `must_yield` have to
+// give way, approaches not listed have the right of way and may always go.
+// this models priority, not live gap-acceptance -- a yielding approach is
+// always allowed to go once it's its turn to decide, the same way a green
+// light is, rather than waiting for a gap in crossing traffic.
+#[derive(Debug, Clone)]
+pub struct ControlStopSign {
+    must_yield: HashSet<Direction>,
+}
+
+impl ControlStopSign {
+    // every approach stops (a 4-way stop); remove a road's two directions
+    // with `give_priority` to make it the through-road instead.
+    pub fn all_way() -> Self {
+        Self {
+            must_yield: [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    pub fn give_priority(mut self, direction: Direction) -> Self {
+        self.must_yield.remove(&direction);
+        self
+    }
+
+    pub fn must_yield(&self, incoming: Direction) -> bool {
+        self.must_yield.contains(&incoming)
+    }
+}
+
+// a third intersection-control option alongside TrafficSignal/StopSign:
+// movements are granted on a first-come, first-served basis against whatever
+// else has already been accepted at this intersection this tick, rather than
+// following a fixed cycle or priority ordering. mirrors A/B Street's
+// IntersectionSimState, simplified: reservations are granted/denied fresh
+// every tick (see Grid::intersection_reservations) instead of being held
+// until a car physically clears the intersection's footprint, since this
+// repo has no notion of "inside the intersection" geometry distinct from the
+// RoadSection a car is entering. a car denied a reservation this tick just
+// sits still and retries next tick -- once the deterministic per-tick car
+// order (see Grid::tick_cars) has let the winning car advance past the
+// conflict, the loser gets through on a later tick, so this can't deadlock
+// the way two cars permanently yielding to each other would.
+//
+// unlike TrafficSignal/StopSign, whether a movement is allowed isn't a
+// function of (incoming, decision) alone -- it depends on what else has
+// already been accepted this tick -- so IntersectionControl::allows can't
+// answer for this variant; Grid::movement_blocked special-cases it instead.
+#[derive(Debug, Clone, Copy)]
+pub struct IntersectionReservationPolicy {
+    // when set, a movement is also denied if the section it leads into is
+    // already at capacity, so a car can't advance into an intersection it
+    // won't be clear of -- the intersection equivalent of the "don't block
+    // the box" check possible_decisions_avoiding_full_sections already does
+    // for ordinary sections.
+    pub dont_block_the_box: bool,
+}
+
+impl IntersectionReservationPolicy {
+    pub fn new(dont_block_the_box: bool) -> Self {
+        Self { dont_block_the_box }
+    }
+
+    // whether two simultaneous movements through the same intersection would
+    // physically cross paths. deliberately simplified -- e.g. unlike
+    // Graph::cost_to's turn cost, the answer doesn't depend on DrivingSide --
+    // good enough to rule out genuine T-bone conflicts while still letting
+    // compatible movements (e.g. opposing through-traffic) proceed together.
+    pub fn movements_conflict(a: (Direction, CarDecision), b: (Direction, CarDecision)) -> bool {
+        let (incoming_a, decision_a) = a;
+        let (incoming_b, decision_b) = b;
+
+        if incoming_a == incoming_b {
+            // same approach: never conflicts, the same way one signal phase
+            // can admit every decision from one incoming direction at once
+            // (see SignalPhase::all_movements_from).
+            return false;
+        }
+
+        if incoming_b == incoming_a.inverted() {
+            // opposing approaches: straight/straight, straight/right and
+            // left/left all pass without crossing; only a left turn across
+            // an opposing through movement actually conflicts.
+            return matches!(
+                (decision_a, decision_b),
+                (CarDecision::TurnLeft, CarDecision::GoStraight)
+                    | (CarDecision::GoStraight, CarDecision::TurnLeft)
+            );
+        }
+
+        // perpendicular approaches: every pairing crosses the other's path
+        // except two simultaneous right turns, which each peel off away from
+        // the intersection before reaching the other's lane.
+        !(decision_a == CarDecision::TurnRight && decision_b == CarDecision::TurnRight)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum IntersectionControl {
+    TrafficSignal(ControlTrafficSignal),
+    StopSign(ControlStopSign),
+    Reservation(IntersectionReservationPolicy),
+}
+
+impl IntersectionControl {
+    // the default plan every auto-generated intersection gets: a
+    // two-phase signal (see ControlTrafficSignal::default_plan).
+    pub fn default_signal(phase_ticks: usize) -> Self {
+        Self::TrafficSignal(ControlTrafficSignal::default_plan(phase_ticks))
+    }
+
+    pub fn tick(&mut self) {
+        if let Self::TrafficSignal(signal) = self {
+            signal.tick();
+        }
+    }
+
+    // whether a car approaching from `incoming` may take `decision` right
+    // now. only meaningful for the two static policies -- Reservation's
+    // answer depends on what else has been accepted this tick, so
+    // Grid::movement_blocked never calls this for that variant.
+    pub fn allows(&self, incoming: Direction, decision: CarDecision) -> bool {
+        match self {
+            Self::TrafficSignal(signal) => signal.allows(incoming, decision),
+            Self::StopSign(stop_sign) => !stop_sign.must_yield(incoming),
+            Self::Reservation(_) => unreachable!(
+                "Reservation policy is special-cased by Grid::movement_blocked, not IntersectionControl::allows"
+            ),
+        }
+    }
+}
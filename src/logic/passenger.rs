@@ -1,11 +1,14 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use macroquad::color::{Color, ORANGE, RED};
+use pyo3::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use super::{car::CarPosition, grid_util::PassengerEvent};
+use super::{car::CarPosition, demand::WeightedArea, grid_util::PassengerEvent};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[pyclass]
 pub struct PassengerId(usize);
 
 impl PassengerId {
@@ -16,7 +19,7 @@ impl PassengerId {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Passenger {
     pub id: PassengerId,
     pub start: CarPosition,
@@ -26,6 +29,49 @@ pub struct Passenger {
     pub start_tick: usize,
 }
 
+// macroquad::color::Color isn't serde-enabled, so Grid::snapshot/restore
+// round-trips it as plain (r, g, b, a) floats rather than deriving through
+// it directly -- see PyGridState's PyGridStateData for the same
+// shadow-struct idea applied to its own non-serde fields.
+#[derive(Serialize, Deserialize)]
+struct PassengerData {
+    id: PassengerId,
+    start: CarPosition,
+    destination: CarPosition,
+    car_on_its_way: bool,
+    colour: (f32, f32, f32, f32),
+    start_tick: usize,
+}
+
+impl Serialize for Passenger {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = PassengerData {
+            id: self.id,
+            start: self.start,
+            destination: self.destination,
+            car_on_its_way: self.car_on_its_way,
+            colour: (self.colour.r, self.colour.g, self.colour.b, self.colour.a),
+            start_tick: self.start_tick,
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Passenger {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = PassengerData::deserialize(deserializer)?;
+        let (r, g, b, a) = data.colour;
+        Ok(Self {
+            id: data.id,
+            start: data.start,
+            destination: data.destination,
+            car_on_its_way: data.car_on_its_way,
+            colour: Color { r, g, b, a },
+            start_tick: data.start_tick,
+        })
+    }
+}
+
 impl Passenger {
     pub fn random(mut rng: impl Rng, current_tick: usize) -> Self {
         Self {
@@ -38,31 +84,27 @@ impl Passenger {
         }
     }
 
+    // used to brute-force a start/destination by rejection-sampling
+    // CarPosition::random_in_area up to 1000 times (and, for the
+    // destination, an unbounded loop that could spin forever on an area
+    // with no valid sections at all). WeightedArea replaces that with a
+    // precomputed index over every valid RoadSection in the area, so
+    // sampling is a single weighted draw instead of a retry loop -- see
+    // demand.rs.
     pub fn random_in_event(mut rng: impl Rng, current_tick: usize, event: &PassengerEvent) -> Self {
-        let mut start = None;
-        for _ in 0..1000 {
-            let start_pos = CarPosition::random_in_area(&mut rng, event.start_area);
-            let (sx, sy) = start_pos.road_section.checkerboard_coords();
-            let (sx1, sy1, sx2, sy2) = event.start_area;
-            if sx >= sx1 && sx <= sx2 && sy >= sy1 && sy <= sy2 {
-                start = Some(start_pos);
-                break;
-            }
-        }
-        let start = start.expect("Could not find a random start position in event");
+        let start_area = WeightedArea::new(event.start_area, &[]);
+        let destination_area = WeightedArea::new(event.destination_area, &[]);
 
-        let mut destination = None;
-        loop {
-            let destination_pos = CarPosition::random_in_area(&mut rng, event.destination_area);
-            let (dx, dy) = destination_pos.road_section.checkerboard_coords();
-            let (dx1, dy1, dx2, dy2) = event.destination_area;
-            if dx >= dx1 && dx <= dx2 && dy >= dy1 && dy <= dy2 {
-                destination = Some(destination_pos);
-                break;
-            }
-        }
-        let destination =
-            destination.expect("Could not find a random destination position in event");
+        let start = CarPosition {
+            road_section: start_area.sample(&mut rng),
+            position_in_section: 0,
+            in_charging_station: None,
+        };
+        let destination = CarPosition {
+            road_section: destination_area.sample(&mut rng),
+            position_in_section: 0,
+            in_charging_station: None,
+        };
 
         Self {
             id: PassengerId::next(),
@@ -1,11 +1,18 @@
-use std::{collections::VecDeque, hash::Hash};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    hash::Hash,
+};
 
 use pathfinding::directed::astar::astar;
+use rand::Rng;
 
 use super::{
     car::{CarDecision, CarPosition},
     car_agent::AgentAction,
-    util::RoadSection,
+    ev::ChargingStationId,
+    grid_util::SectionCongestion,
+    util::{DrivingSide, HashMap, HashSet, RoadSection, TurnType},
 };
 
 #[derive(Clone, Debug)]
@@ -14,37 +21,116 @@ pub struct Path {
     // last element is destination
     pub sections: VecDeque<RoadSection>,
 
+    // the maneuver type of each transition in `sections`, i.e.
+    // turns[i] == sections[i].turn_type(sections[i + 1]). one shorter than
+    // `sections` (no transition out of the last one), so higher layers can
+    // reason about maneuver difficulty without recomputing it themselves.
+    pub turns: VecDeque<TurnType>,
+
     // need to store car position in section as well
     pub destination: CarPosition,
 
     pub cost: usize,
 
     pub action: Option<AgentAction>,
+
+    // how far `cost` can be from the true shortest distance, as a multiple
+    // (1.0 = exact, since this equals the heuristic_weight Path::find_weighted
+    // was called with). lets callers that care about exactness (e.g. RL
+    // observations) tell a weighted/approximate path apart from an optimal one.
+    pub suboptimality_bound: f64,
 }
 
 impl Path {
-    pub fn find(start: CarPosition, destination: CarPosition) -> Self {
+    // a plain, closure-unaware shortest path: used for distance estimates
+    // and geometry helpers (CarPosition::path_to/distance_to, fixed rail/
+    // shuttle legs) that have no Grid to ask about live section closures,
+    // and for the routes Car::find_path gives FixedRoute/PythonAgent/
+    // GymAgent cars, which therefore don't avoid closed sections either --
+    // see find_weighted's closed_sections param for a search that does.
+    pub fn find(start: CarPosition, destination: CarPosition, heuristic: Heuristic) -> Self {
+        Self::find_weighted(
+            start,
+            destination,
+            heuristic,
+            1.0,
+            None,
+            &HashSet::default(),
+            usize::MAX,
+        )
+    }
+
+    // like find(), but multiplies the heuristic by `heuristic_weight` (>= 1.0)
+    // before handing it to astar, and, if `congestion` is given, adds a
+    // BPR-style penalty to each section's real cost based on how occupied it
+    // currently is. weight 1.0 and no congestion map is today's optimal
+    // search; a larger weight biases the search towards the goal, expanding
+    // far fewer nodes at the cost of paths up to `heuristic_weight`x the true
+    // optimum, while the congestion penalty only ever raises edge costs, so
+    // plain Manhattan distance stays a valid lower bound either way.
+    //
+    // `beam_width` caps the open set that beam_search keeps around at any
+    // one time to the best `beam_width` nodes (by g + h); usize::MAX skips
+    // beam_search entirely and runs the exact, unbounded astar below, while
+    // a finite width bounds memory/time on huge graphs at the risk of
+    // beam_search failing to find a path, in which case this falls back to
+    // the same exact astar.
+    //
+    // `closed_sections` are hard-excluded from the search -- entering one is
+    // never a valid successor (see Graph::successors) -- unlike congestion,
+    // which only makes a section more expensive, never impassable. still
+    // panics via the "No path to destination" expect below if closing a
+    // section disconnects the graph entirely; a closure this repo's grid
+    // topology can actually produce is rare enough (every section has more
+    // than one way in/out) that surfacing a clean error for it is future
+    // work, not attempted here.
+    pub fn find_weighted(
+        start: CarPosition,
+        destination: CarPosition,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Self {
+        assert!(heuristic_weight >= 1.0);
+
         if start.road_section == destination.road_section
             && start.position_in_section <= destination.position_in_section
         {
             return Self {
                 sections: VecDeque::from([start.road_section]),
+                turns: VecDeque::new(),
                 destination,
                 cost: (destination.position_in_section - start.position_in_section),
                 action: None,
+                suboptimality_bound: 1.0,
             };
         }
 
         let graph = Graph {
             start,
             destination,
+            congestion,
+            closed: closed_sections,
         };
 
         let start = graph.start_node();
 
         // let node = |index: NodeIndex| &graph.nodes[index];
 
-        let heuristic = |node: &Node| -> usize { node.manhattan_distance(destination) };
+        let heuristic = |node: &Node| -> usize {
+            let manhattan = node.manhattan_distance(destination);
+            let estimate = match heuristic {
+                // plain manhattan distance is already a valid (if weak) lower
+                // bound, so it's always safe to take the max with it
+                Heuristic::Manhattan => manhattan,
+                Heuristic::Alt(landmarks) => {
+                    manhattan.max(landmarks.estimate(node.section(), destination.road_section))
+                }
+            };
+            (estimate as f64 * heuristic_weight) as usize
+        };
         let successors = |node: &Node| {
             // let node = node(*i);
             // let successors = graph.successors(node);
@@ -64,21 +150,34 @@ impl Path {
         };
         let reached_goal = |node: &Node| -> bool { node.is_end_node(destination) };
 
-        let (sections, mut cost) =
-            astar(&start, successors, heuristic, reached_goal).expect("No path to destination");
+        let (sections, mut cost) = if beam_width == usize::MAX {
+            astar(&start, successors, heuristic, reached_goal).expect("No path to destination")
+        } else {
+            beam_search(&start, successors, heuristic, reached_goal, beam_width)
+                .or_else(|| astar(&start, successors, heuristic, reached_goal))
+                .expect("No path to destination")
+        };
         cost += destination.position_in_section;
 
-        let sections = sections.into_iter().map(|node| node.section()).collect();
+        let sections: VecDeque<RoadSection> =
+            sections.into_iter().map(|node| node.section()).collect();
+        let turns = sections
+            .iter()
+            .zip(sections.iter().skip(1))
+            .map(|(section, next)| section.turn_type(*next))
+            .collect();
         Self {
             sections,
+            turns,
             destination,
             cost,
             action: None,
+            suboptimality_bound: heuristic_weight,
         }
     }
 
     pub fn distance(start: CarPosition, end: CarPosition, speed: usize) -> usize {
-        let path = Self::find(start, end);
+        let path = Self::find(start, end, Heuristic::Manhattan);
         path.cost
     }
 
@@ -90,12 +189,421 @@ impl Path {
     }
 }
 
-struct Graph {
+// a route visiting several waypoints in order, e.g. a car's onboard
+// passengers' dropoffs interleaved with a newly assigned pickup. each leg is
+// a plain Path with its own AgentAction (PickUp/DropOff), so the current leg
+// drives next_decision exactly like a single-destination trip would; once
+// its destination is reached the caller advances to the next leg.
+#[derive(Clone, Debug)]
+pub struct MultiLegPath {
+    legs: VecDeque<Path>,
+    pub cost: usize,
+}
+
+impl MultiLegPath {
+    // above this many waypoints, enumerating every ordering gets too slow to
+    // run once per tick, so plan() falls back to best_order_greedy
+    const MAX_EXACT_WAYPOINTS: usize = 6;
+
+    // finds the cheapest order to visit `waypoints` from `start`, subject to
+    // `precedes(a, b)` (waypoint at index a must come before index b in the
+    // chosen order), then stitches a Path for each consecutive leg.
+    //
+    // for small waypoint counts this walks every permutation, like the TSP
+    // permutation search over visiting order; beyond that it falls back to a
+    // greedy nearest-next insertion, which is fast but no longer guaranteed
+    // optimal.
+    pub fn plan(
+        start: CarPosition,
+        waypoints: Vec<(CarPosition, AgentAction)>,
+        precedes: impl Fn(usize, usize) -> bool,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Self {
+        assert!(!waypoints.is_empty());
+
+        let order = if waypoints.len() <= Self::MAX_EXACT_WAYPOINTS {
+            Self::best_order_exact(
+                start,
+                &waypoints,
+                &precedes,
+                heuristic,
+                heuristic_weight,
+                congestion,
+                closed_sections,
+                beam_width,
+            )
+        } else {
+            Self::best_order_greedy(
+                start,
+                &waypoints,
+                &precedes,
+                heuristic,
+                heuristic_weight,
+                congestion,
+                closed_sections,
+                beam_width,
+            )
+        };
+
+        let ordered = order.into_iter().map(|i| waypoints[i]).collect();
+        Self::stitch(
+            start,
+            ordered,
+            heuristic,
+            heuristic_weight,
+            congestion,
+            closed_sections,
+            beam_width,
+        )
+    }
+
+    // like plan() for a single destination, but if that trip would take more
+    // cells than `range` lets this car travel, inserts a forced leg through
+    // the nearest charging station within range first (an
+    // AgentAction::ChargeBattery waypoint before the real destination) --
+    // like routing through a ParkNearBuilding waypoint. falls back to
+    // driving straight for `destination` if no station is in range either
+    // (the stuck_end_dist case: Grid's out_of_battery handling already
+    // covers a car that actually runs out mid-trip).
+    pub fn plan_chargeable(
+        start: CarPosition,
+        destination: CarPosition,
+        action: AgentAction,
+        range: usize,
+        charging_stations: impl Iterator<Item = (ChargingStationId, CarPosition)>,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Self {
+        let direct_cost = Path::find_weighted(
+            start,
+            destination,
+            heuristic,
+            heuristic_weight,
+            congestion,
+            closed_sections,
+            beam_width,
+        )
+        .cost;
+
+        if direct_cost <= range {
+            return Self::stitch(
+                start,
+                vec![(destination, action)],
+                heuristic,
+                heuristic_weight,
+                congestion,
+                closed_sections,
+                beam_width,
+            );
+        }
+
+        let nearest_in_range = charging_stations
+            .map(|(id, entrance)| (id, entrance, start.distance_to(entrance)))
+            .filter(|&(_, _, distance)| distance <= range)
+            .min_by_key(|&(_, _, distance)| distance);
+
+        let waypoints = match nearest_in_range {
+            Some((cs_id, entrance, _)) => {
+                vec![(entrance, AgentAction::ChargeBattery(cs_id)), (destination, action)]
+            }
+            None => vec![(destination, action)], // stuck_end_dist: drive for it anyway
+        };
+
+        Self::stitch(
+            start,
+            waypoints,
+            heuristic,
+            heuristic_weight,
+            congestion,
+            closed_sections,
+            beam_width,
+        )
+    }
+
+    // stitches `start -> waypoints[0] -> waypoints[1] -> ...` into one
+    // MultiLegPath, one Path::find per leg.
+    fn stitch(
+        start: CarPosition,
+        waypoints: Vec<(CarPosition, AgentAction)>,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Self {
+        let mut from = start;
+        let mut cost = 0;
+        let mut legs = VecDeque::with_capacity(waypoints.len());
+
+        for (destination, action) in waypoints {
+            let mut leg = Path::find_weighted(
+                from,
+                destination,
+                heuristic,
+                heuristic_weight,
+                congestion,
+                closed_sections,
+                beam_width,
+            );
+            cost += leg.cost;
+            leg.action = Some(action);
+            from = destination;
+            legs.push_back(leg);
+        }
+
+        Self { legs, cost }
+    }
+
+    // exhaustively tries every permutation of 0..waypoints.len() that
+    // satisfies `precedes`, returning the cheapest one
+    fn best_order_exact(
+        start: CarPosition,
+        waypoints: &[(CarPosition, AgentAction)],
+        precedes: &impl Fn(usize, usize) -> bool,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..waypoints.len()).collect();
+        let mut best_order = order.clone();
+        let mut best_cost = usize::MAX;
+
+        loop {
+            if Self::respects_precedence(&order, precedes) {
+                let cost = Self::order_cost(
+                    start,
+                    &order,
+                    waypoints,
+                    heuristic,
+                    heuristic_weight,
+                    congestion,
+                    closed_sections,
+                    beam_width,
+                );
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_order = order.clone();
+                }
+            }
+
+            if !Self::next_permutation(&mut order) {
+                break;
+            }
+        }
+
+        best_order
+    }
+
+    // greedily picks, at every step, the cheapest next waypoint among those
+    // whose precedence constraints are already satisfied
+    fn best_order_greedy(
+        start: CarPosition,
+        waypoints: &[(CarPosition, AgentAction)],
+        precedes: &impl Fn(usize, usize) -> bool,
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> Vec<usize> {
+        let mut visited = vec![false; waypoints.len()];
+        let mut order = Vec::with_capacity(waypoints.len());
+        let mut from = start;
+
+        for _ in 0..waypoints.len() {
+            let next = (0..waypoints.len())
+                .filter(|&i| !visited[i])
+                .filter(|&i| (0..waypoints.len()).all(|j| !precedes(j, i) || visited[j]))
+                .min_by_key(|&i| {
+                    Path::find_weighted(
+                        from,
+                        waypoints[i].0,
+                        heuristic,
+                        heuristic_weight,
+                        congestion,
+                        closed_sections,
+                        beam_width,
+                    )
+                    .cost
+                })
+                .expect("at least one waypoint has no unmet precedence left");
+
+            visited[next] = true;
+            order.push(next);
+            from = waypoints[next].0;
+        }
+
+        order
+    }
+
+    fn respects_precedence(order: &[usize], precedes: &impl Fn(usize, usize) -> bool) -> bool {
+        for (pos_a, &a) in order.iter().enumerate() {
+            for &b in &order[..pos_a] {
+                if precedes(a, b) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn order_cost(
+        start: CarPosition,
+        order: &[usize],
+        waypoints: &[(CarPosition, AgentAction)],
+        heuristic: Heuristic,
+        heuristic_weight: f64,
+        congestion: Option<&HashMap<RoadSection, SectionCongestion>>,
+        closed_sections: &HashSet<RoadSection>,
+        beam_width: usize,
+    ) -> usize {
+        let mut from = start;
+        let mut cost = 0;
+        for &i in order {
+            let (destination, _) = waypoints[i];
+            cost += Path::find_weighted(
+                from,
+                destination,
+                heuristic,
+                heuristic_weight,
+                congestion,
+                closed_sections,
+                beam_width,
+            )
+            .cost;
+            from = destination;
+        }
+        cost
+    }
+
+    // classic in-place lexicographic next permutation; returns false (and
+    // leaves `order` sorted ascending) once the last permutation is reached
+    fn next_permutation(order: &mut [usize]) -> bool {
+        let Some(i) = (1..order.len()).rev().find(|&i| order[i - 1] < order[i]) else {
+            order.reverse();
+            return false;
+        };
+
+        let j = (i..order.len())
+            .rev()
+            .find(|&j| order[j] > order[i - 1])
+            .unwrap();
+        order.swap(i - 1, j);
+        order[i..].reverse();
+        true
+    }
+
+    // the leg currently being driven: its sections/destination/action drive
+    // next_decision and the CarPathAgent glue exactly like a plain Path
+    pub fn current_leg(&self) -> &Path {
+        self.legs.front().expect("MultiLegPath always has a leg")
+    }
+
+    pub fn next_decision(&self) -> Option<CarDecision> {
+        self.current_leg().next_decision()
+    }
+
+    // called once the current leg's destination has been reached: drops it
+    // and moves on to the next waypoint. returns false if that was the last
+    // leg, i.e. the whole route is complete.
+    pub fn advance(&mut self) -> bool {
+        if self.legs.len() <= 1 {
+            return false;
+        }
+        self.legs.pop_front();
+        true
+    }
+}
+
+// a bounded-memory alternative to `astar`: keeps the same g + h expansion
+// order, but after every relaxation prunes the open set down to the best
+// `beam_width` nodes, discarding the rest. with `beam_width == usize::MAX`
+// nothing is ever pruned and this explores exactly what `astar` would, but
+// for a finite width it can discard a node that was on the only path to the
+// goal, so it may come back empty on a graph it would otherwise solve --
+// callers should treat `None` as "retry with plain astar", not "no path
+// exists".
+fn beam_search<N, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    beam_width: usize,
+) -> Option<(Vec<N>, usize)>
+where
+    N: Eq + Hash + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, usize)>,
+    FH: FnMut(&N) -> usize,
+    FS: FnMut(&N) -> bool,
+{
+    let mut best_g: HashMap<N, usize> = HashMap::default();
+    let mut parent: HashMap<N, N> = HashMap::default();
+    best_g.insert(start.clone(), 0);
+
+    // the open set: (f, node) pairs not yet expanded, capped at `beam_width`
+    let mut open: Vec<(usize, N)> = vec![(heuristic(start), start.clone())];
+
+    while !open.is_empty() {
+        let best_index = (0..open.len())
+            .min_by_key(|&i| open[i].0)
+            .expect("open is non-empty");
+        let (_, node) = open.swap_remove(best_index);
+        let g = best_g[&node];
+
+        if success(&node) {
+            let mut path = vec![node.clone()];
+            let mut current = node;
+            while let Some(p) = parent.get(&current) {
+                path.push(p.clone());
+                current = p.clone();
+            }
+            path.reverse();
+            return Some((path, g));
+        }
+
+        for (succ, cost) in successors(&node) {
+            let new_g = g + cost;
+            if best_g.get(&succ).is_some_and(|&old| old <= new_g) {
+                continue;
+            }
+            best_g.insert(succ.clone(), new_g);
+            parent.insert(succ.clone(), node.clone());
+            let f = new_g + heuristic(&succ);
+            open.push((f, succ));
+        }
+
+        if open.len() > beam_width {
+            open.sort_by_key(|(f, _)| *f);
+            open.truncate(beam_width);
+        }
+    }
+
+    None
+}
+
+struct Graph<'a> {
     start: CarPosition,
     destination: CarPosition,
+    congestion: Option<&'a HashMap<RoadSection, SectionCongestion>>,
+    closed: &'a HashSet<RoadSection>,
 }
 
-impl Graph {
+impl Graph<'_> {
+    // classic BPR (Bureau of Public Roads) link-performance coefficients:
+    // cost multiplier = 1 + alpha * (load / capacity) ^ beta
+    const CONGESTION_ALPHA: f64 = 0.15;
+    const CONGESTION_BETA: f64 = 4.0;
+
     fn start_node(&self) -> Node {
         Node {
             car_pos: self.start,
@@ -104,21 +612,50 @@ impl Graph {
         }
     }
 
+    // extra ticks an unprotected cross-traffic turn costs, over and above
+    // just driving the distance, to stand in for waiting for a gap in
+    // oncoming traffic (see DrivingSide::crosses_oncoming_traffic)
+    const CROSS_TRAFFIC_TURN_PENALTY: usize = 2;
+    // extra ticks a U-turn costs, standing in for looping round the block;
+    // never actually reached today since RoadSection::possible_decisions
+    // never offers one, but kept alongside the other turn penalties so
+    // turn_penalty stays a complete, reusable table
+    const U_TURN_PENALTY: usize = 6;
+
+    // configurable per-TurnType pathfinding penalty: only ever adds ticks on
+    // top of the distance driven, so manhattan_distance stays an admissible
+    // (if now looser) lower bound and A* stays optimal.
+    fn turn_penalty(turn_type: TurnType, crosses_oncoming_traffic: bool) -> usize {
+        match turn_type {
+            TurnType::Straight => 0,
+            TurnType::UTurn => Self::U_TURN_PENALTY,
+            TurnType::TurnLeft | TurnType::TurnRight if crosses_oncoming_traffic => {
+                Self::CROSS_TRAFFIC_TURN_PENALTY
+            }
+            TurnType::TurnLeft | TurnType::TurnRight => 0,
+        }
+    }
+
     fn successors(&self, node: &Node) -> Vec<(Node, usize)> {
-        
         let possible_decisions = node.car_pos.possible_decisions();
 
         let roads = possible_decisions
             .into_iter()
             .filter(|d| *d != CarDecision::ChargeBattery)
-            .filter_map(|d| node.section().take_decision(d));
-        let car_positions = roads.map(|r| CarPosition {
-            road_section: r,
-            position_in_section: 0,
-            in_charging_station: None,
+            .filter_map(|d| node.section().take_decision(d).map(|r| (d, r)))
+            .filter(|(_, r)| !self.closed.contains(r));
+        let car_positions = roads.map(|(d, r)| {
+            (
+                d,
+                CarPosition {
+                    road_section: r,
+                    position_in_section: 0,
+                    in_charging_station: None,
+                },
+            )
         });
-        let nodes = car_positions.map(|p| {
-            let ticks_after_parent = self.cost_to(node, p);
+        let nodes = car_positions.map(|(d, p)| {
+            let ticks_after_parent = self.cost_to(node, p, d);
             Node {
                 car_pos: p,
                 ticks_after_parent,
@@ -133,11 +670,10 @@ impl Graph {
         let mut successors = Vec::with_capacity(3);
         successors.extend(nodes_and_cost);
         successors
-        // nodes_and_cost
     }
 
-    // the cost to go here from here to a successor
-    fn cost_to(&self, node: &Node, to: CarPosition) -> usize {
+    // the cost to go here from here to a successor, reached by `decision`
+    fn cost_to(&self, node: &Node, to: CarPosition, decision: CarDecision) -> usize {
         assert_ne!(node.car_pos.road_section, to.road_section);
 
         // count the ticks of:
@@ -153,7 +689,23 @@ impl Graph {
         let distance_from_road_start = to.position_in_section;
         let time_from_road_start = distance_from_road_start;
 
-        time_to_road_end + time_from_road_start
+        let mut cost = time_to_road_end + time_from_road_start;
+
+        let turn_type = node.section().turn_type(to.road_section);
+        let crosses_oncoming_traffic = DrivingSide::current().crosses_oncoming_traffic(decision);
+        cost += Self::turn_penalty(turn_type, crosses_oncoming_traffic);
+
+        // BPR-style penalty: only ever raises the real cost of entering an
+        // occupied section, so `manhattan_distance` stays an admissible
+        // (if now looser) lower bound and doesn't need to change.
+        match self.congestion.and_then(|c| c.get(&to.road_section)) {
+            Some(congestion) => {
+                let occupancy = congestion.occupancy as f64;
+                let multiplier = 1.0 + Self::CONGESTION_ALPHA * occupancy.powf(Self::CONGESTION_BETA);
+                ((cost as f64) * multiplier).round() as usize
+            }
+            None => cost,
+        }
     }
 
     // fn add_node(&self, node: Node) -> NodeIndex {
@@ -212,3 +764,238 @@ impl Node {
         self.car_pos.manhattan_distance(destination)
     }
 }
+
+// which lower-bound function Path::find's A* search uses. Manhattan is
+// always admissible but, because sections are directional (one-ways via
+// RoadType), it's often a loose bound, so A* ends up expanding far more
+// nodes than it needs to.
+#[derive(Clone, Copy, Debug)]
+pub enum Heuristic<'a> {
+    Manhattan,
+    // ALT (A*, Landmarks, Triangle inequality): tightens the bound using
+    // precomputed distances to/from a handful of landmark sections. Never
+    // looser than Manhattan, since Path::find takes the max of the two.
+    Alt(&'a Landmarks),
+}
+
+// precomputed ALT landmark tables over the (directed) section graph, built
+// once at grid construction and reused for every Path::find call that opts
+// into Heuristic::Alt.
+//
+// for each landmark l, dist_from[l][v] is the shortest number of ticks from
+// l to section v, and dist_to[l][v] is the shortest number of ticks from v
+// to l. by the triangle inequality, both
+//   dist_to[l][v]   - dist_to[l][t]
+//   dist_from[l][t] - dist_from[l][v]
+// are lower bounds on the true distance from v to t, so the max over all
+// landmarks (clamped at 0, since the graph is directed and these can go
+// negative) is a valid, and usually much tighter, heuristic than Manhattan
+// distance alone.
+#[derive(Debug)]
+pub struct Landmarks {
+    section_index: HashMap<RoadSection, usize>,
+
+    dist_from: Vec<Vec<usize>>, // [landmark][section_index]
+    dist_to: Vec<Vec<usize>>,   // [landmark][section_index]
+}
+
+impl Landmarks {
+    // more landmarks tighten the bound at the cost of more memory/setup time;
+    // 16 is a common choice in the ALT literature for road-sized graphs
+    pub const COUNT: usize = 16;
+
+    pub fn compute() -> Self {
+        let sections = RoadSection::all();
+        let section_index: HashMap<RoadSection, usize> = sections
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(i, section)| (section, i))
+            .collect();
+
+        let forward_adjacency = build_adjacency(&sections, &section_index);
+        let backward_adjacency = Self::reverse_adjacency(&forward_adjacency);
+
+        let landmark_count = Self::COUNT.min(sections.len());
+        let mut dist_from = Vec::with_capacity(landmark_count);
+        let mut dist_to = Vec::with_capacity(landmark_count);
+
+        // farthest-point selection: start from a random section, then
+        // repeatedly add the section whose minimum distance to the
+        // already-chosen landmarks is largest
+        let mut min_dist_to_chosen = vec![usize::MAX; sections.len()];
+        let mut next_index = rand::thread_rng().gen_range(0..sections.len());
+
+        while dist_from.len() < landmark_count {
+            let forward = dijkstra(next_index, &forward_adjacency);
+            let backward = dijkstra(next_index, &backward_adjacency);
+
+            for (i, &d) in forward.iter().enumerate() {
+                min_dist_to_chosen[i] = min_dist_to_chosen[i].min(d);
+            }
+
+            dist_from.push(forward);
+            dist_to.push(backward);
+
+            next_index = min_dist_to_chosen
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, &d)| d)
+                .map(|(i, _)| i)
+                .expect("grid has at least one road section");
+        }
+
+        Self {
+            section_index,
+            dist_from,
+            dist_to,
+        }
+    }
+
+    // lower bound on the shortest-path distance from `from` to `to`
+    pub fn estimate(&self, from: RoadSection, to: RoadSection) -> usize {
+        let Some(&from) = self.section_index.get(&from) else {
+            return 0;
+        };
+        let Some(&to) = self.section_index.get(&to) else {
+            return 0;
+        };
+
+        (0..self.dist_from.len())
+            .map(|l| {
+                let via_to = self.dist_to[l][from].saturating_sub(self.dist_to[l][to]);
+                let via_from = self.dist_from[l][to].saturating_sub(self.dist_from[l][from]);
+                via_to.max(via_from)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn reverse_adjacency(forward: &[Vec<(usize, usize)>]) -> Vec<Vec<(usize, usize)>> {
+        let mut reverse = vec![Vec::new(); forward.len()];
+        for (from, edges) in forward.iter().enumerate() {
+            for &(to, cost) in edges {
+                reverse[to].push((from, cost));
+            }
+        }
+        reverse
+    }
+}
+
+// section -> (neighbour, ticks to enter it) for every section in the grid,
+// mirroring the edges Graph::successors explores (minus ChargeBattery,
+// which doesn't move you to another section). shared by Landmarks and
+// SectionDistances, the two precomputed tables built over this same
+// (directed, unweighted-by-congestion) section graph.
+fn build_adjacency(
+    sections: &[RoadSection],
+    section_index: &HashMap<RoadSection, usize>,
+) -> Vec<Vec<(usize, usize)>> {
+    sections
+        .iter()
+        .map(|section| {
+            section
+                .possible_decisions()
+                .into_iter()
+                .filter(|d| *d != CarDecision::ChargeBattery)
+                .filter_map(|d| section.take_decision(d))
+                .map(|next| (section_index[&next], next.direction.section_capacity()))
+                .collect()
+        })
+        .collect()
+}
+
+fn dijkstra(start: usize, adjacency: &[Vec<(usize, usize)>]) -> Vec<usize> {
+    let mut dist = vec![usize::MAX; adjacency.len()];
+    let mut heap = BinaryHeap::new();
+
+    dist[start] = 0;
+    heap.push(Reverse((0, start)));
+
+    while let Some(Reverse((d, node))) = heap.pop() {
+        if d > dist[node] {
+            continue;
+        }
+
+        for &(next, cost) in &adjacency[node] {
+            let next_dist = d + cost;
+            if next_dist < dist[next] {
+                dist[next] = next_dist;
+                heap.push(Reverse((next_dist, next)));
+            }
+        }
+    }
+
+    dist
+}
+
+// flat all-pairs section distance table: distances[from][to] is the
+// shortest number of ticks from section `from` to section `to`, ignoring
+// position_in_section (the same section-level granularity
+// RoadSection::manhattan_distance already uses as a heuristic). built once
+// at grid construction when GridOpts::precompute_section_distances is set,
+// and looked up by Grid::section_distance instead of calling Path::find --
+// see PyPassenger::idle/riding_at, which used to run a full A* search per
+// passenger per tick build just to report distance_to_destination.
+//
+// trades O(sections^2) memory (u16 per pair -- plenty of headroom for this
+// grid's section count) and an O(sections) batch of Dijkstra runs at
+// startup for O(1) lookups afterwards. unlike ED_LRR's precomp_file, this
+// isn't persisted to disk: the repo has no (de)serialization dependency to
+// build that on top of yet, so it's recomputed every run. on this grid's
+// size that's a one-off cost well under a second, not worth adding a new
+// dependency for until profiling says otherwise.
+#[derive(Debug)]
+pub struct SectionDistances {
+    section_index: HashMap<RoadSection, usize>,
+    section_count: usize,
+    distances: Vec<u16>, // row-major: distances[from * section_count + to]
+}
+
+impl SectionDistances {
+    pub fn compute() -> Self {
+        let sections = RoadSection::all();
+        let section_count = sections.len();
+        let section_index: HashMap<RoadSection, usize> = sections
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(i, section)| (section, i))
+            .collect();
+
+        let adjacency = build_adjacency(&sections, &section_index);
+
+        let mut distances = vec![u16::MAX; section_count * section_count];
+        for from in 0..section_count {
+            let row = dijkstra(from, &adjacency);
+            for (to, &dist) in row.iter().enumerate() {
+                distances[from * section_count + to] = dist.min(u16::MAX as usize) as u16;
+            }
+        }
+
+        Self {
+            section_index,
+            section_count,
+            distances,
+        }
+    }
+
+    // shortest number of ticks from `from` to `to`, or usize::MAX if
+    // unreachable (including when either section is outside the grid --
+    // shouldn't happen, but this is a lookup table, not a panic-worthy
+    // invariant to assert)
+    pub fn get(&self, from: RoadSection, to: RoadSection) -> usize {
+        let Some((&from, &to)) = self
+            .section_index
+            .get(&from)
+            .zip(self.section_index.get(&to))
+        else {
+            return usize::MAX;
+        };
+
+        match self.distances[from * self.section_count + to] {
+            u16::MAX => usize::MAX,
+            dist => dist as usize,
+        }
+    }
+}
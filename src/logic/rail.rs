@@ -0,0 +1,261 @@
+// a rail/tram transit layer that runs its own fixed-route vehicles (Train)
+// down the same road network cars drive on, boarding/alighting passengers
+// automatically at designated stations. this is the fixed-route transit
+// subsystem (RailLine is the route, Train the vehicle, py_grid::PyTrain/
+// PyGridState::trains its Python surface); see TickEvent::TransitBoarded/
+// TransitAlighted for how a ride shows up in PyTickEvents. this is a
+// deliberately bounded slice of the full transit-layer idea: trains aren't
+// yet a first-class Path/MultiLegPath travel mode a car-carrying passenger
+// can be routed through (that would mean teaching pathfinding to plan
+// multi-modal trips, a materially bigger change than this one), and
+// boarding/alighting here is immediate rather than going through the
+// CarPassenger::PickingUp/DroppingOff two-phase dance cars use -- trains
+// have no CarId to hang a per-car TickEvent off, hence the separate
+// Transit* variants keyed by TrainId instead. what's here is real: a
+// configurable-length, multi-cell vehicle that cycles a station-to-station
+// schedule and actually carries passengers between stops.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    car::CarPosition,
+    passenger::Passenger,
+    pathfinding::{Heuristic, Path},
+    util::RoadSection,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub struct RailLineId(usize);
+
+impl RailLineId {
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        Self(NEXT_ID.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub struct TrainId(usize);
+
+impl TrainId {
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        Self(NEXT_ID.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+// one cell of a RailLine's flattened route: which line, and how far along
+// that line's route (RailLine::route) a train's front currently is. unlike
+// RoadSection, this never needs Direction/turn machinery -- a train only
+// ever goes straight and stops at stations, it never makes a decision --
+// so it reuses RoadSection's *idea* of addressing a cell with a pair of
+// indices, not RoadSection's own turning logic.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[pyclass]
+pub struct RailSection {
+    #[pyo3(get)]
+    pub line: RailLineId,
+    #[pyo3(get)]
+    pub index: usize,
+}
+
+// a line's stations and the actual road cells connecting them, cycled
+// through forever exactly like car_agent::ShuttleRoute (see that type's
+// doc comment for the loop_back/there-and-back distinction -- it applies
+// identically here). trains run on the same road cells cars do, so a rail
+// line is just a fixed path through the existing RoadSection graph, the
+// same way a ShuttleRoute is.
+#[derive(Debug)]
+pub struct RailLine {
+    pub id: RailLineId,
+    pub stops: Vec<RoadSection>,
+    pub loop_back: bool,
+
+    // every RoadSection cell along the line's full cycle, stations
+    // included, precomputed once via Path::find between consecutive stops.
+    // route[stop_route_index[i]] == stops[i]
+    route: Vec<RoadSection>,
+    stop_route_index: Vec<usize>,
+}
+
+impl RailLine {
+    pub fn new(stops: Vec<RoadSection>, loop_back: bool) -> Self {
+        assert!(stops.len() >= 2, "a rail line needs at least two stations");
+
+        let leg_pairs: Vec<(usize, usize)> = match loop_back {
+            true => (0..stops.len())
+                .map(|i| (i, (i + 1) % stops.len()))
+                .collect(),
+            false => (0..stops.len() - 1).map(|i| (i, i + 1)).collect(),
+        };
+
+        let mut route = vec![stops[0]];
+        let mut stop_route_index = vec![0];
+
+        for (from, to) in leg_pairs {
+            let leg = Path::find(
+                Self::station_position(stops[from]),
+                Self::station_position(stops[to]),
+                Heuristic::Manhattan,
+            );
+
+            route.extend(leg.sections.iter().skip(1).copied());
+            stop_route_index.push(route.len() - 1);
+        }
+
+        Self {
+            id: RailLineId::next(),
+            stops,
+            loop_back,
+            route,
+            stop_route_index,
+        }
+    }
+
+    fn station_position(station: RoadSection) -> CarPosition {
+        CarPosition {
+            road_section: station,
+            position_in_section: 0,
+            in_charging_station: None,
+        }
+    }
+
+    pub fn section_at(&self, route_index: usize) -> RoadSection {
+        self.route[route_index]
+    }
+
+    pub fn route_len(&self) -> usize {
+        self.route.len()
+    }
+
+    pub fn stop_count(&self) -> usize {
+        self.stops.len()
+    }
+
+    fn stop_route_index(&self, stop_index: usize) -> usize {
+        self.stop_route_index[stop_index]
+    }
+}
+
+// a multi-cell vehicle (train/tram) cycling a RailLine's stations, dwelling
+// `dwell_ticks` at each one to board/alight passengers -- the rail
+// equivalent of car_agent::FixedRoute, just without needing a CarId/
+// CarPathAgent (a train isn't a Car, it has its own per-tick update, see
+// Grid::tick_trains).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Train {
+    pub id: TrainId,
+    pub line: RailLineId,
+    pub length: usize, // how many consecutive route cells the train spans
+    pub capacity: usize,
+    pub passengers: Vec<Passenger>,
+    pub dwell_ticks: usize,
+
+    front_index: usize, // this train's front cell, as an index into RailLine::route
+    forward: bool,      // which way along the route it's headed (flips on a there-and-back line)
+    state: TrainState,
+    dwell_ticks_left: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum TrainState {
+    AtStop(usize),
+    Travelling(usize), // the stop it's heading towards
+}
+
+impl Train {
+    pub fn new(line: &RailLine, length: usize, capacity: usize, dwell_ticks: usize) -> Self {
+        Self {
+            id: TrainId::next(),
+            line: line.id,
+            length,
+            capacity,
+            passengers: Vec::new(),
+            dwell_ticks,
+            front_index: line.stop_route_index(0),
+            forward: true,
+            state: TrainState::AtStop(0),
+            dwell_ticks_left: dwell_ticks,
+        }
+    }
+
+    pub fn front_section(&self, line: &RailLine) -> RoadSection {
+        line.section_at(self.front_index)
+    }
+
+    // the RoadSections this train currently spans, in route order with the
+    // front section last -- the rail
+    // equivalent of CarPosition::occupied_cells, just indexed along a
+    // line's route rather than within a single RoadSection.
+    pub fn occupied_sections(&self, line: &RailLine) -> Vec<RoadSection> {
+        let rear = self
+            .front_index
+            .saturating_sub(self.length.saturating_sub(1));
+        (rear..=self.front_index)
+            .map(|i| line.section_at(i))
+            .collect()
+    }
+
+    // Some(stop index) while dwelling, None while travelling between stops
+    pub fn at_stop(&self) -> Option<usize> {
+        match self.state {
+            TrainState::AtStop(stop) => Some(stop),
+            TrainState::Travelling(_) => None,
+        }
+    }
+
+    // the stop after `from`, and the direction the train will be travelling
+    // once it gets there -- identical ping-pong/loop logic to
+    // car_agent::ShuttleRoute::next_stop.
+    fn next_stop(&self, from: usize, line: &RailLine) -> (usize, bool) {
+        let last = line.stop_count() - 1;
+
+        if line.loop_back {
+            return ((from + 1) % line.stop_count(), true);
+        }
+
+        match (self.forward, from) {
+            (true, c) if c < last => (c + 1, true),
+            (false, c) if c > 0 => (c - 1, false),
+            (true, _) => (from - 1, false),
+            (false, _) => (from + 1, true),
+        }
+    }
+
+    // advances dwell/travel state by one tick. passenger boarding/alighting
+    // is handled separately by Grid::tick_trains, since that needs mutable
+    // access to Grid::waiting_passengers that a Train doesn't have.
+    pub fn tick(&mut self, line: &RailLine) {
+        if let TrainState::AtStop(stop) = self.state {
+            if self.dwell_ticks_left > 0 {
+                self.dwell_ticks_left -= 1;
+                return;
+            }
+
+            let (next_stop, forward) = self.next_stop(stop, line);
+            self.forward = forward;
+            self.state = TrainState::Travelling(next_stop);
+        }
+
+        let TrainState::Travelling(next_stop) = self.state else {
+            unreachable!("just set to Travelling above if we weren't already");
+        };
+
+        self.front_index = match (self.forward, line.loop_back) {
+            (true, true) => (self.front_index + 1) % line.route_len(),
+            (true, false) => self.front_index + 1,
+            (false, _) => self.front_index.saturating_sub(1),
+        };
+
+        if self.front_index == line.stop_route_index(next_stop) {
+            self.state = TrainState::AtStop(next_stop);
+            self.dwell_ticks_left = self.dwell_ticks;
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{
+    car::{CarDecision, CarId, CarPosition},
+    ev::ChargingStationId,
+    passenger::PassengerId,
+    pathfinding::Path,
+};
+
+// what a Router's car is currently trying to accomplish. unlike
+// car_agent::AgentAction (a tag on an already-computed Path, purely for
+// display/bookkeeping), a Goal is what the Router recomputes a Path
+// *towards* once the current one runs out.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Goal {
+    PickUp(PassengerId),
+    DropOff(PassengerId),
+    GoCharge(ChargingStationId),
+    RoamRandomly,
+}
+
+// what the sim should do once a Router's car reaches the end of its
+// path. the Router only ever reports this; applying it (boarding a
+// passenger, entering a charging station, picking a new destination) is
+// the owning agent's job.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ActionAtEnd {
+    StartCharging,
+    BoardPassenger,
+    AlightPassenger,
+    GiveUpAndReroute,
+}
+
+// caches a Path alongside the Goal it was computed for, so an agent can
+// hand out CarDecisions by popping the cached path instead of calling
+// Path::find (or MultiLegPath::plan) every tick. once the path runs dry,
+// next_decision starts returning None and the agent should call
+// action_at_end to find out what to do, then either set_goal to a fresh
+// Goal+Path or, for GiveUpAndReroute, recompute from scratch.
+#[derive(Debug)]
+pub struct Router {
+    pub owner: CarId,
+    goal: Goal,
+    path: Option<Path>,
+}
+
+impl Router {
+    pub fn new(owner: CarId, goal: Goal) -> Self {
+        Self {
+            owner,
+            goal,
+            path: None,
+        }
+    }
+
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+
+    // start driving towards a new goal on a freshly-computed path.
+    pub fn set_goal(&mut self, goal: Goal, path: Path) {
+        self.goal = goal;
+        self.path = Some(path);
+    }
+
+    // true once the car has nothing left to do on the cached path: either
+    // there never was one, or it's been driven all the way to its
+    // destination. mirrors the "same road section as the destination"
+    // check CarPathAgent's blanket get_turn impl already does for
+    // single-leg paths.
+    pub fn reached_goal(&self, car_position: CarPosition) -> bool {
+        let Some(path) = &self.path else {
+            return true;
+        };
+
+        car_position.road_section == path.destination.road_section
+            && car_position.position_in_section >= path.destination.position_in_section
+    }
+
+    // the next CarDecision to drive towards the goal, or None if
+    // reached_goal(car_position) is true and there's nothing left to pop.
+    pub fn next_decision(&self, car_position: CarPosition) -> Option<CarDecision> {
+        let path = self.path.as_ref()?;
+
+        if let Some(decision) = path.next_decision() {
+            return Some(decision);
+        }
+
+        // no more sections to cross, but we might still be driving down
+        // the destination's own section towards its exact position
+        if car_position.road_section == path.destination.road_section
+            && car_position.position_in_section < path.destination.position_in_section
+        {
+            return Some(CarDecision::GoStraight);
+        }
+
+        None
+    }
+
+    // what to do now that reached_goal(car_position) is true. callers
+    // should only call this once the car has actually arrived; calling it
+    // with no path at all is treated the same as having given up.
+    pub fn action_at_end(&self) -> ActionAtEnd {
+        match self.path {
+            None => ActionAtEnd::GiveUpAndReroute,
+            Some(_) => match self.goal {
+                Goal::PickUp(_) => ActionAtEnd::BoardPassenger,
+                Goal::DropOff(_) => ActionAtEnd::AlightPassenger,
+                Goal::GoCharge(_) => ActionAtEnd::StartCharging,
+                Goal::RoamRandomly => ActionAtEnd::GiveUpAndReroute,
+            },
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::VecDeque;
+
+use macroquad::color::Color;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    car::{CarPosition, CarProps},
+    passenger::{Passenger, PassengerId},
+    snapshot::{PendingPassengerSnapshot, ScenarioSnapshot},
+};
+
+// how long to wait before blindly retrying a spawn that failed because its
+// cell(s) were occupied, and how many times to retry before giving up
+// silently -- à la A/B Street's BLIND_RETRY_TO_SPAWN, but bounded instead of
+// retrying forever.
+const BLIND_RETRY_DELAY_TICKS: usize = 5;
+const MAX_BLIND_RETRIES: usize = 20;
+
+// a single scheduled car departure: what to spawn and when, not yet turned
+// into a live Car until its tick comes due (see Scenario::due_cars)
+pub struct CarDeparture {
+    pub tick: usize,
+    pub props: CarProps,
+    pub position: Option<CarPosition>,
+}
+
+// a single scheduled passenger arrival: when a passenger should appear
+// waiting at `start`, bound for `destination`
+#[derive(Clone, Copy)]
+pub struct PassengerArrival {
+    pub tick: usize,
+    pub start: CarPosition,
+    pub destination: CarPosition,
+    pub colour: Color,
+}
+
+// macroquad::color::Color isn't serde-enabled, so this round-trips it as
+// plain (r, g, b, a) floats rather than deriving through it directly -- see
+// Passenger's PassengerData for the same shadow-struct idea applied to the
+// same problem.
+#[derive(Serialize, Deserialize)]
+struct PassengerArrivalData {
+    tick: usize,
+    start: CarPosition,
+    destination: CarPosition,
+    colour: (f32, f32, f32, f32),
+}
+
+impl Serialize for PassengerArrival {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = PassengerArrivalData {
+            tick: self.tick,
+            start: self.start,
+            destination: self.destination,
+            colour: (self.colour.r, self.colour.g, self.colour.b, self.colour.a),
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PassengerArrival {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = PassengerArrivalData::deserialize(deserializer)?;
+        let (r, g, b, a) = data.colour;
+        Ok(Self {
+            tick: data.tick,
+            start: data.start,
+            destination: data.destination,
+            colour: Color { r, g, b, a },
+        })
+    }
+}
+
+// scales or drops entries before a scenario starts, e.g. to dry-run a
+// smaller version of a recorded scenario, or strip one demand pattern out
+// entirely without re-authoring the whole thing
+pub trait ScenarioModifier {
+    fn apply(&self, cars: &mut Vec<CarDeparture>, passengers: &mut Vec<PassengerArrival>);
+}
+
+// keeps only the first `factor` fraction of each list, ordered by tick --
+// i.e. a smaller, earlier slice of the same demand pattern
+pub struct ScaleDemand {
+    pub factor: f32,
+}
+
+impl ScenarioModifier for ScaleDemand {
+    fn apply(&self, cars: &mut Vec<CarDeparture>, passengers: &mut Vec<PassengerArrival>) {
+        let keep = |len: usize| ((len as f32) * self.factor).round() as usize;
+        cars.sort_by_key(|c| c.tick);
+        passengers.sort_by_key(|p| p.tick);
+        cars.truncate(keep(cars.len()));
+        passengers.truncate(keep(passengers.len()));
+    }
+}
+
+// drops every departure/arrival scheduled at or after `after_tick`
+pub struct CancelAfter {
+    pub after_tick: usize,
+}
+
+impl ScenarioModifier for CancelAfter {
+    fn apply(&self, cars: &mut Vec<CarDeparture>, passengers: &mut Vec<PassengerArrival>) {
+        cars.retain(|c| c.tick < self.after_tick);
+        passengers.retain(|p| p.tick < self.after_tick);
+    }
+}
+
+struct PendingRetry<T> {
+    entry: T,
+    retry_at_tick: usize,
+    retries_left: usize,
+}
+
+// a reproducible, timed demand script: every car departure and passenger
+// arrival is decided up front and drained from a seeded RNG, so the same
+// seed always produces the same sequence of spawns regardless of what else
+// happens in the sim. Replaces Grid::add_car's "spawn immediately, brute-
+// force a random free cell" behaviour with "spawn at a specific tick, and if
+// the spot's taken, blindly retry a few ticks later" (see due_cars/retry_car).
+pub struct Scenario {
+    seed: u64,
+    rng: StdRng,
+    car_departures: VecDeque<CarDeparture>,
+    passenger_arrivals: VecDeque<PassengerArrival>,
+    pending_cars: Vec<PendingRetry<CarDeparture>>,
+    pending_passengers: Vec<PendingRetry<PassengerArrival>>,
+}
+
+impl Scenario {
+    pub fn new(
+        seed: u64,
+        mut car_departures: Vec<CarDeparture>,
+        mut passenger_arrivals: Vec<PassengerArrival>,
+        modifiers: &[Box<dyn ScenarioModifier>],
+    ) -> Self {
+        for modifier in modifiers {
+            modifier.apply(&mut car_departures, &mut passenger_arrivals);
+        }
+
+        car_departures.sort_by_key(|c| c.tick);
+        passenger_arrivals.sort_by_key(|p| p.tick);
+
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            car_departures: car_departures.into(),
+            passenger_arrivals: passenger_arrivals.into(),
+            pending_cars: Vec::new(),
+            pending_passengers: Vec::new(),
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    // the seed this scenario's demand script was drawn from -- see
+    // Grid::scenario_seed for what replaying it does and doesn't guarantee.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // every car departure due this tick: freshly scheduled ones plus
+    // anything blind-retrying an earlier failed spawn. each comes with the
+    // retries it has left, to pass back into retry_car on failure.
+    pub fn due_cars(&mut self, current_tick: usize) -> Vec<(CarDeparture, usize)> {
+        let mut due = Vec::new();
+
+        while self.car_departures.front().is_some_and(|c| c.tick <= current_tick) {
+            let departure = self.car_departures.pop_front().unwrap();
+            due.push((departure, MAX_BLIND_RETRIES));
+        }
+
+        let mut i = 0;
+        while i < self.pending_cars.len() {
+            if self.pending_cars[i].retry_at_tick <= current_tick {
+                let pending = self.pending_cars.remove(i);
+                due.push((pending.entry, pending.retries_left));
+            } else {
+                i += 1;
+            }
+        }
+
+        due
+    }
+
+    // queue `departure` to be blindly retried a few ticks from now. once its
+    // retry budget is exhausted it's dropped silently, instead of panicking
+    // the way CarToSpawn::position used to.
+    pub fn retry_car(&mut self, departure: CarDeparture, retries_left: usize, current_tick: usize) {
+        if retries_left == 0 {
+            return;
+        }
+
+        self.pending_cars.push(PendingRetry {
+            entry: departure,
+            retry_at_tick: current_tick + BLIND_RETRY_DELAY_TICKS,
+            retries_left: retries_left - 1,
+        });
+    }
+
+    // same as due_cars, but for passenger arrivals
+    pub fn due_passengers(&mut self, current_tick: usize) -> Vec<(PassengerArrival, usize)> {
+        let mut due = Vec::new();
+
+        while self
+            .passenger_arrivals
+            .front()
+            .is_some_and(|p| p.tick <= current_tick)
+        {
+            let arrival = self.passenger_arrivals.pop_front().unwrap();
+            due.push((arrival, MAX_BLIND_RETRIES));
+        }
+
+        let mut i = 0;
+        while i < self.pending_passengers.len() {
+            if self.pending_passengers[i].retry_at_tick <= current_tick {
+                let pending = self.pending_passengers.remove(i);
+                due.push((pending.entry, pending.retries_left));
+            } else {
+                i += 1;
+            }
+        }
+
+        due
+    }
+
+    // same as retry_car, but for passenger arrivals
+    pub fn retry_passenger(
+        &mut self,
+        arrival: PassengerArrival,
+        retries_left: usize,
+        current_tick: usize,
+    ) {
+        if retries_left == 0 {
+            return;
+        }
+
+        self.pending_passengers.push(PendingRetry {
+            entry: arrival,
+            retry_at_tick: current_tick + BLIND_RETRY_DELAY_TICKS,
+            retries_left: retries_left - 1,
+        });
+    }
+
+    // writes this scenario's passenger demand script out as JSON: the seed
+    // plus every still-scheduled arrival, in depart-tick order. lets an
+    // interesting run's demand curve be captured to disk and replayed later
+    // (via load_json + Grid::from_scenario) to compare dispatch policies
+    // against identical demand, or checked into a regression test fixture.
+    //
+    // scripted car departures aren't round-tripped: CarDeparture holds a
+    // Box<dyn CarAgent>, which has no serde impl and no sensible one to add
+    // (an agent is behaviour, not data) -- this only targets the demand
+    // half of Scenario, replacing the random-per-tick passenger spawning
+    // the title names, not scripted car departures.
+    pub fn save_json(&self, path: impl AsRef<std::path::Path>) {
+        let data = ScenarioData {
+            seed: self.seed,
+            passenger_arrivals: self.passenger_arrivals.iter().copied().collect(),
+        };
+        let json = serde_json::to_string(&data).unwrap();
+        std::fs::write(path, json).unwrap();
+    }
+
+    // the inverse of save_json: a fresh Scenario with no car departures and
+    // no pending retries, ready to hand to Grid::from_scenario/set_scenario.
+    pub fn load_json(path: impl AsRef<std::path::Path>) -> Self {
+        let json = std::fs::read_to_string(path).unwrap();
+        let data: ScenarioData = serde_json::from_str(&json).unwrap();
+        Self::new(data.seed, Vec::new(), data.passenger_arrivals, &[])
+    }
+
+    // the serde-serializable, in-progress half of this Scenario that
+    // Grid::snapshot round-trips -- see ScenarioSnapshot's doc comment for
+    // what's captured and why car_departures/pending_cars aren't.
+    pub(crate) fn snapshot(&self) -> ScenarioSnapshot {
+        ScenarioSnapshot {
+            seed: self.seed,
+            // drawn from a throwaway clone, so snapshotting doesn't itself
+            // consume anything from the live rng -- see GridSnapshot's doc
+            // comment for why this is a reseed rather than an exact resume.
+            rng_reseed: self.rng.clone().gen(),
+            passenger_arrivals: self.passenger_arrivals.iter().copied().collect(),
+            pending_passengers: self
+                .pending_passengers
+                .iter()
+                .map(|p| PendingPassengerSnapshot {
+                    arrival: p.entry,
+                    retry_at_tick: p.retry_at_tick,
+                    retries_left: p.retries_left,
+                })
+                .collect(),
+        }
+    }
+
+    // restores state captured by a prior snapshot() call on this same
+    // Scenario -- car_departures and pending_cars are left untouched, the
+    // same gap snapshot() itself documents.
+    pub(crate) fn restore(&mut self, snapshot: ScenarioSnapshot) {
+        self.seed = snapshot.seed;
+        self.rng = StdRng::seed_from_u64(snapshot.rng_reseed);
+        self.passenger_arrivals = snapshot.passenger_arrivals.into();
+        self.pending_passengers = snapshot
+            .pending_passengers
+            .into_iter()
+            .map(|p| PendingRetry {
+                entry: p.arrival,
+                retry_at_tick: p.retry_at_tick,
+                retries_left: p.retries_left,
+            })
+            .collect();
+    }
+}
+
+// the serde-serializable half of Scenario that save_json/load_json actually
+// round-trip -- see save_json's doc comment for why car_departures (and the
+// in-progress rng/pending-retry state, which only matter mid-run) aren't
+// included.
+#[derive(Serialize, Deserialize)]
+struct ScenarioData {
+    seed: u64,
+    passenger_arrivals: Vec<PassengerArrival>,
+}
+
+// turns a due PassengerArrival into the Passenger the rest of the codebase
+// works with, stamping it with the tick it actually spawned on
+pub fn passenger_from_arrival(arrival: &PassengerArrival, current_tick: usize) -> Passenger {
+    Passenger {
+        id: PassengerId::next(),
+        start: arrival.start,
+        destination: arrival.destination,
+        car_on_its_way: false,
+        colour: arrival.colour,
+        start_tick: current_tick,
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::python::bridge::bridge::PyAction;
+
+use super::{
+    car::{CarId, CarPassenger, CarPosition},
+    ev::{BatteryPercent, ChargingStation, ChargingStationId},
+    grid_util::{GridStats, TrafficLight},
+    passenger::{Passenger, PassengerId},
+    rail::{Train, TrainId},
+    scenario::PassengerArrival,
+    util::RoadSection,
+};
+
+// the variable, per-tick-mutated part of a Car -- deliberately everything
+// Car *except* CarProps (id, agent, colour, speed, discharge_rate, model,
+// length). CarProps.agent is a Box<dyn CarAgent>, which can't be generically
+// serialized or even cloned (PythonAgent wraps a live PyObject, RandomDestination/
+// FixedRoute hold their own mid-route state in a Router/ShuttleRoute that
+// isn't worth round-tripping either) -- so Grid::restore only ever targets a
+// Grid whose self.cars already has the same cars/agents attached that were
+// there at snapshot time, and just overwrites each one's moving parts. see
+// Grid::snapshot's doc comment for what this does and doesn't support.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CarSnapshot {
+    pub position: CarPosition,
+    pub ticks_until_next_movement: usize,
+    pub passengers: Vec<CarPassenger>,
+    pub battery: BatteryPercent,
+    pub recent_actions: VecDeque<PyAction>,
+    pub blocked_by_leader: bool,
+}
+
+// the serde-serializable, in-progress-demand half of a Scenario that
+// Grid::snapshot/restore actually round-trip, mirroring ScenarioData (see
+// Scenario::save_json's doc comment): car_departures/pending_cars aren't
+// included since CarDeparture holds a CarProps (a Box<dyn CarAgent>, which
+// has no serde impl and no sensible one to add). unlike save_json this does
+// capture the in-progress rng/pending-retry state, since a rollout restore
+// mid-scenario needs that to resume exactly where it left off, not just
+// the original demand script.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingPassengerSnapshot {
+    pub arrival: PassengerArrival,
+    pub retry_at_tick: usize,
+    pub retries_left: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScenarioSnapshot {
+    pub seed: u64,
+    pub rng_reseed: u64,
+    pub passenger_arrivals: Vec<PassengerArrival>,
+    pub pending_passengers: Vec<PendingPassengerSnapshot>,
+}
+
+// a point-in-time copy of the cars, passengers, traffic lights, charging
+// stations, trains, scenario, stats and RNGs that Grid::tick mutates, for
+// MCTS-style rollout branching: snapshot once, try several action
+// sequences, restore back to the same point and try again. not captured:
+// intersection_controls' per-tick state and analytics, which restore
+// leaves as they are (section_congestion/section_queues are rebuilt from
+// car positions at the start of every tick anyway). maps are stored as Vec<(K, V)>
+// rather than util::HashMap (an IndexMap) directly, matching the rest of the
+// codebase's convention of projecting maps to Vecs at any serde/Python
+// boundary (see py_grid::PyGridState's other_cars/idle_passengers) rather
+// than relying on IndexMap's own serde support.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GridSnapshot {
+    pub cars: Vec<(CarId, CarSnapshot)>,
+    pub car_positions: Vec<(CarPosition, CarId)>,
+    pub waiting_passengers: Vec<(PassengerId, Passenger)>,
+    pub waiting_passenger_positions: Vec<(CarPosition, PassengerId)>,
+    pub traffic_lights: Vec<(RoadSection, TrafficLight)>,
+    pub charging_stations: Vec<(ChargingStationId, ChargingStation)>,
+    pub trains: Vec<(TrainId, Train)>,
+    pub scenario: Option<ScenarioSnapshot>,
+    pub ticks_passed: usize,
+    pub stats: GridStats,
+
+    // StdRng's internal counter isn't something rand exposes a serializable
+    // handle to, so rather than pull in a second RNG crate just for that,
+    // each restore reseeds passenger_rng/car_rng from a u64 drawn (via a
+    // throwaway clone, so snapshotting itself doesn't consume anything) from
+    // their state at snapshot time. that's not a byte-exact resume of the
+    // pre-snapshot stream, but it is exactly reproducible -- restoring the
+    // same GridSnapshot twice replays the same passenger/car randomness both
+    // times, which is what rollout branching actually needs.
+    pub passenger_rng_reseed: u64,
+    pub car_rng_reseed: u64,
+}
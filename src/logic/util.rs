@@ -1,9 +1,12 @@
+use std::sync::{OnceLock, RwLock};
+
 use gxhash::GxBuildHasher;
 use indexmap::{IndexMap, IndexSet};
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use super::{car::CarDecision, grid::Grid};
+use super::{car::CarDecision, elevation, grid::Grid};
 
 pub type HashMap<K, V> = IndexMap<K, V, GxBuildHasher>;
 pub type HashSet<K> = IndexSet<K, GxBuildHasher>;
@@ -59,7 +62,7 @@ impl Orientation {
     }
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub enum Direction {
     Up,
@@ -110,6 +113,11 @@ impl Direction {
         }
     }
 
+    // how many cars a section in this direction can hold at once
+    pub fn section_capacity(self) -> usize {
+        self.max_position_in_section() + 1
+    }
+
     pub fn clockwise(self) -> Self {
         match self {
             Self::Up => Self::Right,
@@ -148,7 +156,152 @@ impl Direction {
     }
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+// which side of the road traffic drives on, analogous to A/B Street's
+// MapConfig::driving_side. governs which side of the road cars hug and
+// passengers queue (RoadCoords::sidewalk_coords, RoadRenderer's
+// on_positive_side_of_road, CarPosition::leave_charging_station) and which
+// turn crosses oncoming traffic for pathfinding purposes (see
+// Graph::cost_to's turn penalty in pathfinding.rs).
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub enum DrivingSide {
+    Left,  // UK/Australia-style
+    Right, // USA-style
+}
+
+// process-wide fallback for the few call sites below that have no Grid to
+// ask (CarPosition::leave_charging_station, Graph::cost_to -- both pure
+// geometry/pathfinding helpers that predate GridOpts::driving_side and
+// aren't worth threading a driving side through just for this). set once
+// per Grid::new from its GridOpts::driving_side, so it always reflects
+// whichever scenario most recently started; code that does have a Grid or
+// GridRenderer handy (RoadRenderer, RoadCoords) should prefer
+// `grid.opts.driving_side` directly over DrivingSide::current().
+static CURRENT: OnceLock<RwLock<DrivingSide>> = OnceLock::new();
+
+impl DrivingSide {
+    fn current_lock() -> &'static RwLock<DrivingSide> {
+        CURRENT.get_or_init(|| RwLock::new(Self::Left))
+    }
+
+    pub fn current() -> Self {
+        *Self::current_lock().read().unwrap()
+    }
+
+    // called once from Grid::new with GridOpts::driving_side -- see there.
+    pub fn set_current(side: Self) {
+        *Self::current_lock().write().unwrap() = side;
+    }
+
+    pub fn is_left(self) -> bool {
+        matches!(self, Self::Left)
+    }
+
+    // true if `decision` crosses the oncoming lane before completing the
+    // turn: in right-hand traffic that's an unprotected left (the oncoming
+    // lane is to your left), in left-hand traffic it's the mirror image, an
+    // unprotected right.
+    pub fn crosses_oncoming_traffic(self, decision: CarDecision) -> bool {
+        match (self, decision) {
+            (Self::Right, CarDecision::TurnLeft) => true,
+            (Self::Left, CarDecision::TurnRight) => true,
+            _ => false,
+        }
+    }
+}
+
+// classifies a one-step RoadSection transition, analogous to A/B Street's
+// TurnType: what kind of maneuver a car makes going from one section into
+// the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[pyclass]
+pub enum TurnType {
+    Straight,
+    TurnLeft,
+    TurnRight,
+    UTurn,
+}
+
+impl TurnType {
+    // the angle swept turning from the incoming to the outgoing road, the
+    // way a driver would feel it: positive = clockwise (right), negative =
+    // counterclockwise (left). exact on this cardinal grid, purely for
+    // rendering/telemetry that wants a number rather than a variant.
+    pub fn degrees(self) -> i32 {
+        match self {
+            Self::Straight => 0,
+            Self::TurnRight => 90,
+            Self::TurnLeft => -90,
+            Self::UTurn => 180,
+        }
+    }
+}
+
+// per-section road metadata, analogous to OpenTTD's NotRoadTypes: how many
+// lanes a section has, how much faster/slower than the base speed it is,
+// and whether it only carries traffic in one direction.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[pyclass]
+pub struct RoadType {
+    #[pyo3(get)]
+    pub lanes: usize,
+    #[pyo3(get)]
+    pub speed_limit_multiplier: f32,
+    // Some(direction) => traffic may only flow that way
+    #[pyo3(get)]
+    pub one_way: Option<Direction>,
+}
+
+impl RoadType {
+    // two-lane two-way street, the behaviour every section had before
+    // road types existed
+    pub const SIDE_STREET: Self = Self {
+        lanes: 2,
+        speed_limit_multiplier: 1.0,
+        one_way: None,
+    };
+
+    // wide, fast through-road
+    pub const ARTERIAL: Self = Self {
+        lanes: 3,
+        speed_limit_multiplier: 1.5,
+        one_way: None,
+    };
+
+    // narrow one-way street, e.g. an alley
+    const ONE_WAY_LANES: usize = 1;
+    const ONE_WAY_SPEED_MULTIPLIER: f32 = 0.75;
+
+    // deterministic mix of road types across the grid: every 4th road is a
+    // wide fast arterial, every 4th+1 is a narrow one-way street (alternating
+    // direction every other one-way, like real-world one-way avenues), the
+    // rest are ordinary two-lane side streets. kept a pure function of
+    // (orientation, road_index) so both the sim and the renderer agree on
+    // a road's type without needing to share a lookup table.
+    pub fn for_road(orientation: Orientation, road_index: isize) -> Self {
+        match road_index.rem_euclid(4) {
+            0 => Self::ARTERIAL,
+            1 => Self {
+                lanes: Self::ONE_WAY_LANES,
+                speed_limit_multiplier: Self::ONE_WAY_SPEED_MULTIPLIER,
+                one_way: Some(orientation.direction(road_index.rem_euclid(8) == 1)),
+            },
+            _ => Self::SIDE_STREET,
+        }
+    }
+
+    pub fn allows_direction(self, direction: Direction) -> bool {
+        self.one_way.map_or(true, |allowed| allowed == direction)
+    }
+}
+
+impl Default for RoadType {
+    fn default() -> Self {
+        Self::SIDE_STREET
+    }
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct RoadSection {
     // isize (not usize) because it makes rendering traffic lights easier
@@ -244,34 +397,6 @@ impl RoadSection {
         Self::get(direction, road_index, section_index)
     }
 
-    pub fn random_in_area(mut rng: impl Rng, area: (f32, f32, f32, f32)) -> Self {
-        let direction = Direction::random(&mut rng);
-        let (x1, y1, x2, y2) = area;
-
-        for _ in 0..1000 {
-            let road_index;
-            let section_index;
-
-            if direction.is_horizontal() {
-                road_index = rng.gen_range(y1 as usize..y2 as usize);
-                section_index = rng.gen_range(x1 as usize..x2 as usize);
-            } else {
-                road_index = rng.gen_range(x1 as usize..x2 as usize);
-                section_index = rng.gen_range(y1 as usize..y2 as usize);
-            }
-
-            let this = Self::get(direction, road_index, section_index);
-            let (x, y) = this.checkerboard_coords();
-
-            if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
-                return this;
-            }
-
-        }
-
-        panic!("Failed to find random section in area {:?}", area);
-    }
-
     pub fn valid(self) -> Result<(), String> {
         if self.road_index < 0 || self.road_index as usize > self.direction.max_road_index() {
             return Err(format!(
@@ -314,6 +439,11 @@ impl RoadSection {
         }
     }
 
+    // the grid arithmetic for turning is the same regardless of DrivingSide
+    // (TurnLeft/TurnRight are already absolute, from the car's own point of
+    // view); only the real-world cost of a turn -- whether it crosses
+    // oncoming traffic -- depends on which side we drive on, so that's
+    // applied separately in Graph::cost_to rather than here.
     fn turn(self, right: bool) -> Option<Self> {
         let new_direction = match right {
             true => self.direction.clockwise(),
@@ -407,6 +537,28 @@ impl RoadSection {
         }
     }
 
+    pub fn road_type(self) -> RoadType {
+        RoadType::for_road(self.direction.orientation(), self.road_index)
+    }
+
+    pub fn elevation(self) -> f32 {
+        elevation::elevation_at(self.checkerboard_coords())
+    }
+
+    // classifies the one-step transition from this section into `next`
+    // (which must be directly reachable, e.g. via take_decision) by
+    // comparing directions -- the cardinal grid makes this exact, no angle
+    // math needed.
+    pub fn turn_type(self, next: Self) -> TurnType {
+        match next.direction {
+            d if d == self.direction => TurnType::Straight,
+            d if d == self.direction.clockwise() => TurnType::TurnRight,
+            d if d == self.direction.counterclockwise() => TurnType::TurnLeft,
+            d if d == self.direction.inverted() => TurnType::UTurn,
+            _ => unreachable!("{:?} is not directly reachable from {:?}", next, self),
+        }
+    }
+
     pub fn manhattan_distance(self, other: Self) -> usize {
         let self_coords = self.checkerboard_coords();
         let other_coords = other.checkerboard_coords();
@@ -2,21 +2,32 @@ use logic::{car::CarPosition, pathfinding::Path, util::Direction};
 use python::bridge::bridge::{initialise_python, start_python};
 
 mod logic {
+    pub mod analytics;
     pub mod car;
     pub mod car_agent;
+    pub mod car_model;
+    pub mod demand;
+    pub mod elevation;
     pub mod ev;
     pub mod grid;
+    pub mod intersection;
     pub mod passenger;
     pub mod pathfinding;
+    pub mod rail;
+    pub mod router;
+    pub mod scenario;
+    pub mod snapshot;
     pub mod util;
     pub mod grid_util;
 }
 
 mod render {
     pub mod car;
+    pub mod collision;
     pub mod ev;
     pub mod grid;
     pub mod passenger;
+    pub mod rail;
     pub mod render_main;
     pub mod util;
 }
@@ -1,14 +1,22 @@
 use std::sync::OnceLock;
 
+use macroquad::color::SKYBLUE;
 use pyo3::{prelude::*, types::PyList};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     logic::{
-        car::CarPosition,
-        car_agent::AgentAction,
+        analytics::RollingStats,
+        car::{CarId, CarPosition, CarProps},
+        car_agent::{AgentAction, GymAgent, ShuttleRoute},
+        car_model::{car_model_by_name, car_model_registry, CarModel},
+        demand::{DemandEntry, Hotspot, ScenarioGenerator, WeightedArea},
         ev::ChargingStationId,
         grid::{Grid, GridOpts},
-        util::Direction,
+        grid_util::TickEvent,
+        intersection::{ControlStopSign, IntersectionControl, IntersectionReservationPolicy},
+        passenger::PassengerId,
+        util::{Direction, RoadSection},
     },
     render::render_main::GridLock,
 };
@@ -100,9 +108,13 @@ fn exported_python_module<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyModule>
     module.add_class::<Direction>()?;
     module.add_class::<PyCarType>()?;
     module.add_class::<CarPosition>()?;
+    module.add_class::<CarModel>()?;
+    module.add_class::<ShuttleRoute>()?;
 
     module.add_function(wrap_pyfunction!(grid_dimensions, &module)?)?;
     module.add_function(wrap_pyfunction!(calculate_distance, &module)?)?;
+    module.add_function(wrap_pyfunction!(car_model_by_name, &module)?)?;
+    module.add_function(wrap_pyfunction!(car_model_registry, &module)?)?;
 
     Ok(module)
 }
@@ -110,12 +122,56 @@ fn exported_python_module<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyModule>
 #[pyclass]
 pub struct PyGridEnv {
     grid_ref: GridLock,
+
+    // stashed from py_new purely so reset() can rebuild an equivalent Grid
+    // from scratch -- python_agents itself is consumed into
+    // PythonAgentWrapper objects by the time py_new returns, so this is
+    // what gets cloned instead.
+    opts: GridOpts,
+    agents: Vec<PythonAgentWrapper>,
+
+    // the one GymAgent-driven car reset()/step() control directly, when
+    // gym_mode was requested -- see PyGridEnv::spawn_gym_car. None if this
+    // env only uses the agent-callback mode.
+    gym_car_id: Option<CarId>,
+
+    // this env's render_main registry id (see render_main::new_grid), set
+    // when constructed with render=true so many envs can each render
+    // without clobbering each other's entry. deregistered in Drop so the
+    // render thread stops touching this env's grid once it's gone.
+    render_id: Option<usize>,
+}
+
+impl Drop for PyGridEnv {
+    fn drop(&mut self) {
+        if let Some(id) = self.render_id {
+            crate::render::render_main::remove_grid(id);
+        }
+    }
 }
 
-#[pymethods]
 impl PyGridEnv {
-    #[new]
-    fn py_new(python_agents: Py<PyList>, opts: GridOpts, render: bool) -> Self {
+    // spawns the one GymAgent-driven car a gym_mode env drives via
+    // step()/reset() -- queued the same way Grid::new seeds its npc/agent/
+    // shuttle cars, via Grid::add_car, just from outside Grid::new itself
+    // since GymAgent isn't one of the agent kinds GridOpts knows how to
+    // build.
+    fn spawn_gym_car(grid: &mut Grid, opts: &GridOpts) -> CarId {
+        let gym_props = CarProps::new(
+            GymAgent::new(),
+            Grid::CAR_SPEED,
+            opts.discharge_rate,
+            SKYBLUE,
+        );
+        let gym_car_id = gym_props.id;
+        grid.add_car(gym_props, None);
+        gym_car_id
+    }
+
+    // unwraps a Python list of agent callback objects into the
+    // PythonAgentWrapper Grid::new/from_demand_generator expect -- shared by
+    // py_new and from_demand_generator, the two PyGridEnv constructors.
+    fn extract_python_agents(python_agents: Py<PyList>) -> Vec<PythonAgentWrapper> {
         let mut agents = vec![];
         Python::with_gil(|py| {
             let python_agents = python_agents.bind(py);
@@ -124,23 +180,325 @@ impl PyGridEnv {
                 agents.push(agent);
             }
         });
+        agents
+    }
 
-        let grid = Grid::new(opts, agents);
-        let grid_ref = GridLock::new(grid);
+    // the demand.rs equivalent of the (origin, destination, weight) tuples
+    // from_demand_generator's `entries` param accepts: turns the pyo3-facing
+    // (area, hotspots) shape into the Hotspot list WeightedArea::new expects.
+    fn build_weighted_area(
+        area: (f32, f32, f32, f32),
+        hotspots: Vec<((f32, f32, f32, f32), f32)>,
+    ) -> WeightedArea {
+        let hotspots: Vec<Hotspot> = hotspots
+            .into_iter()
+            .map(|(area, weight)| Hotspot { area, weight })
+            .collect();
+        WeightedArea::new(area, &hotspots)
+    }
+}
+
+#[pymethods]
+impl PyGridEnv {
+    #[new]
+    fn py_new(
+        python_agents: Py<PyList>,
+        opts: GridOpts,
+        render: bool,
+        gym_mode: bool,
+        // opt-in frame recording (see GridLock::with_recording) -- None
+        // disables it, Some(dir) dumps every rendered frame to `dir` as a
+        // numbered PNG. Only takes effect when `render` is also true, since
+        // there's nothing to capture a frame of otherwise.
+        record_output_dir: Option<String>,
+    ) -> Self {
+        let agents = Self::extract_python_agents(python_agents);
+
+        let mut grid = Grid::new(opts.clone(), agents.clone());
+        let gym_car_id = gym_mode.then(|| Self::spawn_gym_car(&mut grid, &opts));
+
+        let mut grid_ref = GridLock::new(grid);
+        if let Some(output_dir) = record_output_dir {
+            grid_ref = grid_ref.with_recording(output_dir.into());
+        }
+
+        let render_id = render.then(|| crate::render::render_main::new_grid(grid_ref.clone()));
 
-        if render {
-            let grid_ref = grid_ref.clone();
-            std::thread::spawn(move || {
-                crate::render::render_main::new_grid(grid_ref);
-            });
+        Self {
+            grid_ref,
+            opts,
+            agents,
+            gym_car_id,
+            render_id,
         }
+    }
 
-        Self { grid_ref }
+    // a PyGridEnv seeded from a hand-authored demand pattern instead of the
+    // usual per-tick random passenger spawning -- see
+    // Grid::from_demand_generator's doc comment. `entries` is the pyo3-facing
+    // shape of a Vec<DemandEntry>: each one is (origin_area, origin_hotspots,
+    // destination_area, destination_hotspots, spawn_rate_per_tick, window),
+    // where an area is (x1, y1, x2, y2) and a hotspot is (area, weight) --
+    // see Hotspot/WeightedArea::new for what those mean. not usable in
+    // gym_mode: from_demand_generator's whole point is scripted passenger
+    // arrivals, which wouldn't interact with GymAgent's step()/reset() loop
+    // any differently than py_new's usual random spawning does.
+    #[staticmethod]
+    fn from_demand_generator(
+        python_agents: Py<PyList>,
+        opts: GridOpts,
+        render: bool,
+        entries: Vec<(
+            (f32, f32, f32, f32),
+            Vec<((f32, f32, f32, f32), f32)>,
+            (f32, f32, f32, f32),
+            Vec<((f32, f32, f32, f32), f32)>,
+            f64,
+            (usize, Option<usize>),
+        )>,
+        seed: u64,
+        last_tick: usize,
+    ) -> Self {
+        let agents = Self::extract_python_agents(python_agents);
+
+        let demand_entries = entries
+            .into_iter()
+            .map(
+                |(
+                    origin_area,
+                    origin_hotspots,
+                    destination_area,
+                    destination_hotspots,
+                    spawn_rate_per_tick,
+                    window,
+                )| {
+                    DemandEntry {
+                        origin: Self::build_weighted_area(origin_area, origin_hotspots),
+                        destination: Self::build_weighted_area(
+                            destination_area,
+                            destination_hotspots,
+                        ),
+                        spawn_rate_per_tick,
+                        window,
+                    }
+                },
+            )
+            .collect();
+        let generator = ScenarioGenerator::new(demand_entries);
+
+        let grid =
+            Grid::from_demand_generator(opts.clone(), agents.clone(), &generator, seed, last_tick);
+        let grid_ref = GridLock::new(grid);
+        let render_id = render.then(|| crate::render::render_main::new_grid(grid_ref.clone()));
+
+        Self {
+            grid_ref,
+            opts,
+            agents,
+            gym_car_id: None,
+            render_id,
+        }
     }
 
     fn tick(&self) {
         self.grid_ref.lock().tick();
     }
+
+    // see Grid::scenario_seed for what replaying this seed does and doesn't
+    // guarantee.
+    fn scenario_seed(&self) -> Option<u64> {
+        self.grid_ref.lock().scenario_seed()
+    }
+
+    // the master seed (GridOpts::seed) this episode's passenger/car RNGs
+    // were derived from -- construct a fresh GridOpts with this same seed
+    // to replay the episode's passenger placement, respawn slots and
+    // spawn-rate rolls exactly (see Grid::seed's doc comment for the
+    // call sites this does and doesn't cover).
+    fn seed(&self) -> u64 {
+        self.grid_ref.lock().seed()
+    }
+
+    // the classic Gym reset(): rebuilds the Grid from scratch (same opts
+    // and python agents as construction) and ticks once so the gym car,
+    // just re-queued via spawn_gym_car, actually exists in Grid::cars (see
+    // Grid::add_car -- a freshly queued car isn't realised into a real Car
+    // until a later tick_cars) before handing back its first observation.
+    // panics if this env wasn't constructed with gym_mode.
+    fn reset(&mut self) -> PyGridState {
+        assert!(
+            self.gym_car_id.is_some(),
+            "PyGridEnv::reset() called without gym_mode"
+        );
+
+        let mut grid = Grid::new(self.opts.clone(), self.agents.clone());
+        let gym_car_id = Self::spawn_gym_car(&mut grid, &self.opts);
+        grid.tick();
+
+        let state = PyGridState::build(&grid).with_pov(grid.car(gym_car_id));
+
+        self.gym_car_id = Some(gym_car_id);
+        *self.grid_ref.lock() = grid;
+
+        state
+    }
+
+    // the classic Gym step(): injects `action` into the gym car via
+    // Grid::set_gym_action, advances the grid one tick, and returns the new
+    // POV state alongside a reward/terminated/truncated/info tuple.
+    //
+    // reward is a minimal placeholder -- +1 per passenger the gym car drops
+    // off this tick, 0 otherwise -- since no reward-shaping concept exists
+    // anywhere else in this codebase to build on; callers wanting different
+    // shaping should compute their own from the returned PyGridState/
+    // TickEvents. terminated/truncated are always false: the simulation has
+    // no natural episode end (passenger demand never stops) or built-in
+    // episode-length cap, so wrap this env in something like gymnasium's
+    // TimeLimit if truncation is wanted.
+    //
+    // there's deliberately no observation_space/action_space descriptor
+    // here beyond action_space_size: PyGridState is a structured object,
+    // not a fixed-shape tensor, so there's no tensor shape to report without
+    // inventing one Python isn't actually given.
+    fn step(
+        &self,
+        action: PyAction,
+    ) -> (
+        PyGridState,
+        f64,
+        bool,
+        bool,
+        std::collections::HashMap<String, String>,
+    ) {
+        let gym_car_id = self
+            .gym_car_id
+            .expect("PyGridEnv::step() called without gym_mode");
+
+        let mut grid = self.grid_ref.lock();
+        grid.set_gym_action(gym_car_id, action);
+        grid.tick();
+
+        let reward = grid
+            .tick_events
+            .iter()
+            .filter(|event| {
+                matches!(event, TickEvent::PassengerDroppedOff(car_id, _) if *car_id == gym_car_id)
+            })
+            .count() as f64;
+
+        let state = PyGridState::build(&grid).with_pov(grid.car(gym_car_id));
+
+        (
+            state,
+            reward,
+            false,
+            false,
+            std::collections::HashMap::new(),
+        )
+    }
+
+    // the discrete action count implied by pick_up_passenger/
+    // drop_off_passenger's n_closest range (see PyAction's raw/n_closest
+    // convention and GridOpts::passenger_radius) plus the 4 HeadTowards
+    // directions and 1 ChargeBattery -- lets Python build a Discrete action
+    // space without hardcoding this layout itself. purely a convenience:
+    // `raw` is caller-assigned (see PyAction's static constructors), so
+    // nothing requires Python to actually use this enumeration.
+    fn action_space_size(&self) -> usize {
+        2 * (self.opts.passenger_radius + 1) + Direction::all().len() + 1
+    }
+
+    // lifetime-totals-plus-rolling-window snapshot of Grid::analytics, over
+    // the last `window_ticks` ticks (clamped to however much history
+    // Analytics has kept -- see RollingStats::window_ticks). PyGridState
+    // already exposes rolling_stats/time_series/*_distribution on whatever
+    // grid it was built from, but a caller that only has a PyGridEnv (e.g.
+    // between step() calls) needs this to log reward-shaping signals or
+    // compare agents without re-deriving everything from raw PyGridState.
+    fn analytics(&self, window_ticks: usize) -> RollingStats {
+        self.grid_ref.lock().rolling_stats(window_ticks)
+    }
+
+    // reverses a prior Grid::assign_car_to_passenger/
+    // assign_car_to_passenger_pooled call -- for an external dispatcher
+    // (see assign_car_to_passenger_pooled's doc comment) that's comparing
+    // several candidate cars for the same passenger and wants to back out
+    // of a tentative assignment it's decided not to commit to, instead of
+    // leaving the car committed to a pickup it was only being considered for.
+    fn unassign_car_from_passenger(&self, car_id: CarId, passenger_id: PassengerId) {
+        self.grid_ref
+            .lock()
+            .unassign_car_from_passenger(car_id, passenger_id);
+    }
+
+    // closes `section` to traffic -- see Grid::close_section. any car
+    // currently routed through it has invalidate_path called on its agent;
+    // agents that override it replan around the closure on their next turn,
+    // the rest keep their current path (see CarPathAgent::invalidate_path).
+    fn close_section(&self, section: RoadSection) {
+        self.grid_ref.lock().close_section(section);
+    }
+
+    // reopens a section closed by close_section -- see Grid::open_section.
+    // a no-op if `section` isn't currently closed.
+    fn open_section(&self, section: RoadSection) {
+        self.grid_ref.lock().open_section(section);
+    }
+
+    // switches `section`'s intersection back to the default two-phase
+    // traffic signal (see IntersectionControl::default_signal), e.g. to
+    // undo a prior set_intersection_stop_sign/set_intersection_reservation
+    // call.
+    fn set_intersection_signal(&self, section: RoadSection) {
+        let signal = IntersectionControl::default_signal(Grid::TRAFFIC_LIGHT_TOGGLE_TICKS);
+        self.grid_ref
+            .lock()
+            .set_intersection_policy(section, signal);
+    }
+
+    // switches `section`'s intersection to an all-way stop sign, optionally
+    // giving one road priority (through-traffic on that road doesn't have
+    // to stop) -- see ControlStopSign::all_way/give_priority.
+    fn set_intersection_stop_sign(
+        &self,
+        section: RoadSection,
+        priority_direction: Option<Direction>,
+    ) {
+        let mut stop_sign = ControlStopSign::all_way();
+        if let Some(direction) = priority_direction {
+            stop_sign = stop_sign.give_priority(direction);
+        }
+        self.grid_ref
+            .lock()
+            .set_intersection_policy(section, IntersectionControl::StopSign(stop_sign));
+    }
+
+    // switches `section`'s intersection to first-come-first-served
+    // reservations instead of a fixed signal/priority ordering -- see
+    // IntersectionReservationPolicy.
+    fn set_intersection_reservation(&self, section: RoadSection, dont_block_the_box: bool) {
+        let policy = IntersectionReservationPolicy::new(dont_block_the_box);
+        self.grid_ref
+            .lock()
+            .set_intersection_policy(section, IntersectionControl::Reservation(policy));
+    }
+
+    // a bincode-encoded point-in-time copy of this grid's per-tick-mutated
+    // state -- see Grid::snapshot's doc comment. mirrors
+    // PyGridState::to_bytes's bincode-over-JSON choice, since this is meant
+    // for MCTS-style rollout branching: snapshot once, step through several
+    // action sequences, restore() back and try the next one.
+    fn snapshot(&self) -> Vec<u8> {
+        bincode::serialize(&self.grid_ref.lock().snapshot()).unwrap()
+    }
+
+    // restores state captured by a prior snapshot() call on this same
+    // PyGridEnv -- see Grid::restore's doc comment for what this does and
+    // doesn't overwrite.
+    fn restore(&self, snapshot: &[u8]) {
+        let snapshot = bincode::deserialize(snapshot).unwrap();
+        self.grid_ref.lock().restore(snapshot);
+    }
 }
 
 #[derive(Clone)]
@@ -187,7 +545,7 @@ impl PythonAgentWrapper {
 // might want to replace with pytorch tensor or something
 type RawAction = Option<usize>;
 
-#[derive(Clone, Copy, Hash, Debug)]
+#[derive(Clone, Copy, Hash, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyAction {
     #[pyo3(get)]
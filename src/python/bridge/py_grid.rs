@@ -1,20 +1,24 @@
-use std::collections::BinaryHeap;
+use std::{collections::BinaryHeap, rc::Rc};
 
 use pyo3::prelude::*;
+use rstar::{primitives::GeomWithData, PointDistance, RTree};
+use serde::{Deserialize, Serialize};
 
 use crate::logic::{
+    analytics::{Analytics, Distribution, RollingStats, TimeSeriesBucket},
     car::{Car, CarId, CarPassenger, CarPosition, NextCarPosition},
     ev::{ChargingStation, ChargingStationId},
     grid::Grid,
     grid_util::{GridOpts, GridStats, TickEvent},
+    intersection::IntersectionId,
     passenger::{Passenger, PassengerId},
-    pathfinding::Path,
-    util::{Direction, RoadSection},
+    rail::{RailLine, Train, TrainId},
+    util::{hashmap_with_capacity, Direction, HashMap, RoadSection},
 };
 
 use super::bridge::PyAction;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone)]
 #[pyclass]
 pub struct PyGridState {
     #[pyo3(get)]
@@ -34,8 +38,16 @@ pub struct PyGridState {
     other_cars: Vec<PyCar>,
     #[pyo3(get)]
     idle_passengers: Vec<PyPassenger>,
+    // only populated when opts.cluster_radius is Some -- see
+    // cluster_idle_passengers. empty (not an error) when clustering is off,
+    // so Python can always read this field and check is-empty instead of
+    // branching on opts.
+    #[pyo3(get)]
+    idle_passenger_clusters: Vec<PyPassengerCluster>,
     #[pyo3(get)]
     charging_stations: Vec<PyChargingStation>,
+    #[pyo3(get)]
+    trains: Vec<PyTrain>,
 
     #[pyo3(get)]
     ticks_passed: usize,
@@ -46,6 +58,162 @@ pub struct PyGridState {
 
     car_radius: usize,
     passenger_radius: usize,
+
+    // spatial indices of other_cars/idle_passengers, keyed by their
+    // (road, section) checkerboard coordinates -- an admissible lower bound
+    // on the true road distance distance_to computes (same bound
+    // RoadSection::manhattan_distance relies on). with_pov() walks these via
+    // nearest_n_by_tree to prune most candidates before paying for a
+    // distance_to call, instead of computing it for every car/passenger.
+    // built once per tick in build(), wrapped in Rc so with_pov()'s
+    // per-POV-car clone of Self is cheap rather than rebuilding the tree.
+    // excluded from Debug/PartialEq: purely a derived cache of the fields
+    // above, not state in its own right.
+    car_tree: Rc<RTree<GeomWithData<[f32; 2], CarId>>>,
+    passenger_tree: Rc<RTree<GeomWithData<[f32; 2], PassengerId>>>,
+
+    // snapshot of Grid::analytics's ring buffer, cloned once per tick in
+    // build() (not once per with_pov() POV car) and wrapped in Rc for the
+    // same reason as the trees above. rolling_stats() folds it on demand.
+    analytics: Rc<Analytics>,
+}
+
+impl std::fmt::Debug for PyGridState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PyGridState")
+            .field("opts", &self.opts)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("pov_car", &self.pov_car)
+            .field("can_turn", &self.can_turn)
+            .field("other_cars", &self.other_cars)
+            .field("idle_passengers", &self.idle_passengers)
+            .field("idle_passenger_clusters", &self.idle_passenger_clusters)
+            .field("charging_stations", &self.charging_stations)
+            .field("trains", &self.trains)
+            .field("ticks_passed", &self.ticks_passed)
+            .field("events", &self.events)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for PyGridState {
+    fn eq(&self, other: &Self) -> bool {
+        self.opts == other.opts
+            && self.width == other.width
+            && self.height == other.height
+            && self.pov_car == other.pov_car
+            && self.can_turn == other.can_turn
+            && self.other_cars == other.other_cars
+            && self.idle_passengers == other.idle_passengers
+            && self.idle_passenger_clusters == other.idle_passenger_clusters
+            && self.charging_stations == other.charging_stations
+            && self.trains == other.trains
+            && self.ticks_passed == other.ticks_passed
+            && self.events == other.events
+            && self.stats == other.stats
+    }
+}
+
+// PyGridState can't just #[derive(Serialize, Deserialize)]: car_tree/
+// passenger_tree/analytics are derived caches (Rc<RTree<..>>, Rc<Analytics>)
+// built by build()/with_pov(), not canonical state. this shadow struct holds
+// only the real fields; car_radius/passenger_radius are included since
+// build() needs them to re-derive the trees. on deserialize, the trees are
+// rebuilt the same way build() does it, and analytics comes back as a fresh
+// Analytics::new(opts.time_series_bucket_ticks) -- a restored snapshot
+// resumes with an empty rolling window and time series rather than the
+// original episode's throughput/latency history, which is an honest gap:
+// both are derived views over ticks the snapshot doesn't retain, not state
+// of its own.
+#[derive(Serialize, Deserialize)]
+struct PyGridStateData {
+    opts: GridOpts,
+    width: usize,
+    height: usize,
+    pov_car: Option<PyCar>,
+    can_turn: Option<bool>,
+    other_cars: Vec<PyCar>,
+    idle_passengers: Vec<PyPassenger>,
+    idle_passenger_clusters: Vec<PyPassengerCluster>,
+    charging_stations: Vec<PyChargingStation>,
+    trains: Vec<PyTrain>,
+    ticks_passed: usize,
+    events: PyTickEvents,
+    stats: GridStats,
+    car_radius: usize,
+    passenger_radius: usize,
+}
+
+impl Serialize for PyGridState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = PyGridStateData {
+            opts: self.opts.clone(),
+            width: self.width,
+            height: self.height,
+            pov_car: self.pov_car.clone(),
+            can_turn: self.can_turn,
+            other_cars: self.other_cars.clone(),
+            idle_passengers: self.idle_passengers.clone(),
+            idle_passenger_clusters: self.idle_passenger_clusters.clone(),
+            charging_stations: self.charging_stations.clone(),
+            trains: self.trains.clone(),
+            ticks_passed: self.ticks_passed,
+            events: self.events.clone(),
+            stats: self.stats.clone(),
+            car_radius: self.car_radius,
+            passenger_radius: self.passenger_radius,
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PyGridState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = PyGridStateData::deserialize(deserializer)?;
+
+        let car_tree = Rc::new(RTree::bulk_load(
+            data.other_cars
+                .iter()
+                .chain(data.pov_car.iter())
+                .map(|car| {
+                    let (x, y) = RoadSection::from(car.pos).checkerboard_coords();
+                    GeomWithData::new([x, y], car.id)
+                })
+                .collect(),
+        ));
+        let passenger_tree = Rc::new(RTree::bulk_load(
+            data.idle_passengers
+                .iter()
+                .map(|passenger| {
+                    let (x, y) = RoadSection::from(passenger.pos).checkerboard_coords();
+                    GeomWithData::new([x, y], passenger.id)
+                })
+                .collect(),
+        ));
+
+        Ok(Self {
+            opts: data.opts,
+            width: data.width,
+            height: data.height,
+            pov_car: data.pov_car,
+            can_turn: data.can_turn,
+            other_cars: data.other_cars,
+            idle_passengers: data.idle_passengers,
+            idle_passenger_clusters: data.idle_passenger_clusters,
+            charging_stations: data.charging_stations,
+            trains: data.trains,
+            ticks_passed: data.ticks_passed,
+            events: data.events,
+            stats: data.stats,
+            car_radius: data.car_radius,
+            passenger_radius: data.passenger_radius,
+            car_tree,
+            passenger_tree,
+            analytics: Rc::new(Analytics::new(data.opts.time_series_bucket_ticks)),
+        })
+    }
 }
 
 #[pymethods]
@@ -71,6 +239,69 @@ impl PyGridState {
                 .iter()
                 .map(|car| car.passengers.len())
                 .sum::<usize>()
+            + self
+                .trains
+                .iter()
+                .map(|train| train.passengers.len())
+                .sum::<usize>()
+    }
+
+    // throughput/latency/utilization aggregates over the last `window_ticks`
+    // ticks -- see analytics::Analytics. lets RL training loops and
+    // dashboards read these curves directly instead of post-processing the
+    // stats.csv that write_stats() below appends to.
+    fn rolling_stats(&self, window_ticks: usize) -> RollingStats {
+        self.analytics.rolling_stats(window_ticks)
+    }
+
+    // the whole episode's history of opts.time_series_bucket_ticks-wide
+    // windows -- section entry counts (congestion hotspots, and per-
+    // intersection throughput since TrafficLight is keyed the same way) and
+    // every passenger wait/trip duration, bucket by bucket. unlike
+    // rolling_stats above, nothing here is evicted, so this is what a
+    // dashboard plots a whole run's congestion/wait-time curves from.
+    fn time_series(&self) -> Vec<TimeSeriesBucket> {
+        self.analytics.time_series()
+    }
+
+    // p50/p90/max wait/trip time and per-section throughput over the last
+    // `window_ticks` ticks -- a fuller-shaped rolling counterpart to
+    // rolling_stats' single mean/p95_wait_ticks, for reward shaping or
+    // dashboards that want more than one percentile.
+    fn wait_time_distribution(&self, window_ticks: usize) -> Distribution {
+        self.analytics.wait_time_distribution(window_ticks)
+    }
+
+    fn trip_time_distribution(&self, window_ticks: usize) -> Distribution {
+        self.analytics.trip_time_distribution(window_ticks)
+    }
+
+    fn throughput_by_section(&self, window_ticks: usize) -> Vec<(RoadSection, usize)> {
+        self.analytics.throughput_by_section(window_ticks)
+    }
+
+    // a compact on-disk/over-the-wire snapshot, e.g. for checkpointing an RL
+    // training run. see PyGridState's Serialize/Deserialize impl above for
+    // what is and isn't preserved (notably: analytics' rolling window isn't).
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bincode::deserialize(bytes).unwrap()
+    }
+
+    // same snapshot, as human-readable JSON -- handy for inspecting a
+    // checkpoint by hand or diffing two of them, where to_bytes()'s bincode
+    // output isn't.
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> Self {
+        serde_json::from_str(json).unwrap()
     }
 
     fn write_stats(&self) {
@@ -99,7 +330,7 @@ impl PyGridState {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyCoords {
     #[pyo3(get)]
@@ -115,14 +346,14 @@ pub struct PyCoords {
     pos_in_section: Option<usize>,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub enum PyCarType {
     Agent,
     Npc,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyCar {
     pub id: CarId,
@@ -141,16 +372,24 @@ pub struct PyCar {
     ticks_since_out_of_battery: usize,
     #[pyo3(get)]
     active_action: Option<PyAction>,
+    #[pyo3(get)]
+    blocked_by_leader: bool,
+    #[pyo3(get)]
+    length: usize,
+    // (stop_index, at_stop) for a car driven by a FixedRoute shuttle agent,
+    // None for every other agent
+    #[pyo3(get)]
+    shuttle_status: Option<(usize, bool)>,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub enum PyPassengerState {
     Idle,
     Riding,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyPassenger {
     pub id: PassengerId,
@@ -166,7 +405,29 @@ pub struct PyPassenger {
     distance_to_destination: usize,
 }
 
-#[derive(PartialEq, Eq, Clone, Default, Debug)]
+// a group of idle passengers within cluster_radius road distance of each
+// other (single-link: every member is within cluster_radius of *some* other
+// member, not necessarily the centroid) -- see with_pov's cluster_idle_
+// passengers call. only produced when GridOpts::cluster_radius is Some.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub struct PyPassengerCluster {
+    // the member whose position is closest to the cluster's mean checkerboard
+    // coordinate -- an actual passenger's position, not an interpolated point
+    // that might not even be a valid RoadSection.
+    #[pyo3(get)]
+    centroid: PyCoords,
+    #[pyo3(get)]
+    count: usize,
+    #[pyo3(get)]
+    mean_ticks_since_request: f32,
+    #[pyo3(get)]
+    mean_distance_to_destination: f32,
+    #[pyo3(get)]
+    member_ids: Vec<PassengerId>,
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyTickEvents {
     #[pyo3(get)]
@@ -180,9 +441,44 @@ pub struct PyTickEvents {
     // building a PyCar from a CarToSpawn, which is too much effort
     // knowing that python only cares about the vec length atm.
     car_out_of_battery: Vec<(CarId, PyCoords)>,
+    // (trailing car, leading car, contested position) -- see TickEvent::Collision
+    #[pyo3(get)]
+    collisions: Vec<(CarId, CarId, PyCoords)>,
+
+    // (train, passenger, boarding/alighting station) -- see
+    // TickEvent::TransitBoarded/TransitAlighted
+    #[pyo3(get)]
+    transit_boarded: Vec<(TrainId, PyPassenger, PyCoords)>,
+    #[pyo3(get)]
+    transit_alighted: Vec<(TrainId, PyPassenger, PyCoords)>,
+
+    // (car, station) -- see TickEvent::CarQueuedForCharging/CarStartedCharging
+    #[pyo3(get)]
+    car_queued_for_charging: Vec<(CarId, ChargingStationId)>,
+    #[pyo3(get)]
+    car_started_charging: Vec<(CarId, ChargingStationId)>,
+
+    // see TickEvent::SectionClosed/SectionReopened/PassengerStartUnreachable
+    #[pyo3(get)]
+    section_closed: Vec<RoadSection>,
+    #[pyo3(get)]
+    section_reopened: Vec<RoadSection>,
+    #[pyo3(get)]
+    passenger_start_unreachable: Vec<PassengerId>,
+
+    // (car, intersection) -- see
+    // TickEvent::IntersectionReservationGranted/IntersectionReservationDenied
+    #[pyo3(get)]
+    intersection_reservation_granted: Vec<(CarId, IntersectionId)>,
+    #[pyo3(get)]
+    intersection_reservation_denied: Vec<(CarId, IntersectionId)>,
+
+    // see TickEvent::PassengerAbandoned
+    #[pyo3(get)]
+    passenger_abandoned: Vec<PassengerId>,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 #[pyclass]
 pub struct PyChargingStation {
     pub id: ChargingStationId,
@@ -195,6 +491,35 @@ pub struct PyChargingStation {
     charging_speed: f32,
     #[pyo3(get)]
     cars: Vec<PyCar>,
+    #[pyo3(get)]
+    queue_length: usize,
+    #[pyo3(get)]
+    estimated_wait_ticks: usize,
+}
+
+// a train's current position plus the line it's running, denormalised onto
+// one object (rather than a separate PyRailLine python would have to join
+// against) since today there's only ever the one fixed demo line -- see
+// Grid::rail_lines.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[pyclass]
+pub struct PyTrain {
+    pub id: TrainId,
+
+    #[pyo3(get)]
+    front: PyCoords,
+    #[pyo3(get)]
+    occupied: Vec<PyCoords>,
+    #[pyo3(get)]
+    capacity: usize,
+    #[pyo3(get)]
+    passengers: Vec<PyPassenger>,
+    #[pyo3(get)]
+    at_stop: Option<usize>,
+    #[pyo3(get)]
+    line_stops: Vec<PyCoords>,
+    #[pyo3(get)]
+    line_loop_back: bool,
 }
 
 impl PyGridState {
@@ -208,13 +533,13 @@ impl PyGridState {
         // === process idle passengers ===
         let idle_passengers = grid
             .waiting_passengers()
-            .map(|passenger| PyPassenger::idle(passenger, ticks_passed))
+            .map(|passenger| PyPassenger::idle(passenger, grid, ticks_passed))
             .collect::<Vec<_>>();
 
         //  === process cars ===
         let cars = grid
             .cars()
-            .map(|car| PyCar::build(car, ticks_passed))
+            .map(|car| PyCar::build(car, grid, ticks_passed))
             .collect::<Vec<_>>();
 
         // === process charging stations ===
@@ -224,9 +549,35 @@ impl PyGridState {
             .map(|station| PyChargingStation::build(station, grid))
             .collect::<Vec<_>>();
 
+        // === process trains ===
+        let trains = grid
+            .trains()
+            .map(|train| PyTrain::build(train, grid.rail_line(train.line), grid))
+            .collect::<Vec<_>>();
+
         // === process events ===
         let events = PyTickEvents::build(grid);
 
+        // === build spatial indices for with_pov() ===
+        let car_tree = Rc::new(RTree::bulk_load(
+            cars.iter()
+                .map(|car| {
+                    let (x, y) = RoadSection::from(car.pos).checkerboard_coords();
+                    GeomWithData::new([x, y], car.id)
+                })
+                .collect(),
+        ));
+        let passenger_tree = Rc::new(RTree::bulk_load(
+            idle_passengers
+                .iter()
+                .map(|passenger| {
+                    let (x, y) = RoadSection::from(passenger.pos).checkerboard_coords();
+                    GeomWithData::new([x, y], passenger.id)
+                })
+                .collect(),
+        ));
+        let analytics = Rc::new(grid.analytics().clone());
+
         // === return ===
         Self {
             opts: grid.opts.clone(),
@@ -238,7 +589,9 @@ impl PyGridState {
 
             other_cars: cars,
             idle_passengers,
+            idle_passenger_clusters: Vec::new(), // filled in by with_pov(), which has the pov car to seed/sort from
             charging_stations,
+            trains,
 
             ticks_passed,
             events,
@@ -246,6 +599,10 @@ impl PyGridState {
             car_radius: grid.opts.car_radius,
             passenger_radius: grid.opts.passenger_radius,
             stats: grid.stats.clone(),
+
+            car_tree,
+            passenger_tree,
+            analytics,
         }
     }
 
@@ -266,14 +623,51 @@ impl PyGridState {
         let can_turn = matches!(next_position, NextCarPosition::MustChoose);
         this.can_turn = Some(can_turn);
 
-        // sort passengers by closest to car
-        let val = |passenger: &PyPassenger| pov_car.position.distance_to(passenger.pos.into());
-        this.idle_passengers =
-            lowest_n_sorted(this.idle_passengers.into_iter(), self.passenger_radius, val);
+        let pov_point = {
+            let (x, y) = pov_car.position.road_section.checkerboard_coords();
+            [x, y]
+        };
 
-        // sort cars by closest to pov car
-        let val = |car: &PyCar| pov_car.position.distance_to(car.pos.into());
-        this.other_cars = lowest_n_sorted(this.other_cars.into_iter(), self.car_radius, val);
+        // sort passengers by closest to car, pruning most of them via
+        // self.passenger_tree before paying for distance_to (see
+        // nearest_n_by_tree)
+        let mut passenger_positions: HashMap<PassengerId, CarPosition> =
+            hashmap_with_capacity(this.idle_passengers.len());
+        for passenger in &this.idle_passengers {
+            passenger_positions.insert(passenger.id, passenger.pos.into());
+        }
+        let nearest_passengers = nearest_n_by_tree(
+            &self.passenger_tree,
+            pov_point,
+            self.passenger_radius,
+            |id| {
+                passenger_positions
+                    .get(&id)
+                    .map(|&pos| pov_car.position.distance_to(pos))
+            },
+        );
+        this.idle_passengers = reorder_by_ids(this.idle_passengers, &nearest_passengers, |p| p.id);
+
+        // group idle passengers into PyPassengerCluster entries -- see
+        // cluster_idle_passengers. off by default (empty Vec) unless
+        // opts.cluster_radius is set.
+        if let Some(cluster_radius) = this.opts.cluster_radius {
+            this.idle_passenger_clusters =
+                cluster_idle_passengers(&this.idle_passengers, pov_car.position, cluster_radius);
+        }
+
+        // sort cars by closest to pov car, same pruning via self.car_tree
+        let mut car_positions: HashMap<CarId, CarPosition> =
+            hashmap_with_capacity(this.other_cars.len());
+        for car in &this.other_cars {
+            car_positions.insert(car.id, car.pos.into());
+        }
+        let nearest_cars = nearest_n_by_tree(&self.car_tree, pov_point, self.car_radius, |id| {
+            car_positions
+                .get(&id)
+                .map(|&pos| pov_car.position.distance_to(pos))
+        });
+        this.other_cars = reorder_by_ids(this.other_cars, &nearest_cars, |c| c.id);
 
         // only include events by this car
         this.events
@@ -294,100 +688,204 @@ impl PyGridState {
     }
 }
 
-pub fn lowest_n_sorted<I, F, V>(iter: I, n: usize, mut val: F) -> Vec<I::Item>
-where
-    I: Iterator + std::fmt::Debug,
-    I::Item: PartialEq + Eq + std::fmt::Debug,
-    F: FnMut(&I::Item) -> V,
-    V: Ord + PartialEq + Eq + std::fmt::Debug,
-{
-    // takes an iterator, get the lowest n elements and sorts them
-    // at the front of the returned vector. all the other elements will be
-    // there too, but beyond n elements, are not sorted.
-
-    #[derive(PartialEq, Eq)]
-    struct Item<T, V>
-    where
-        T: PartialEq + Eq,
-        V: Ord + PartialEq + Eq,
-    {
-        item: T,
-        value: V,
-    }
-
-    impl<T, V> PartialOrd for Item<T, V>
-    where
-        T: PartialEq + Eq,
-        V: Ord + PartialEq + Eq,
-    {
+// walks `tree`'s nearest_neighbor_iter in increasing *Euclidean* order,
+// returning up to `n` ids sorted by the true distance `true_distance`
+// computes for each (which, via CarPosition::distance_to, can fall through
+// to a full Path::find -- this is the expensive call we're trying to avoid
+// paying for every candidate). stops as soon as the next candidate's
+// Euclidean distance alone exceeds the worst true distance already in the
+// heap: sound because Euclidean <= true road distance on this grid (the
+// same bound RoadSection::manhattan_distance relies on as an A* heuristic),
+// so nothing farther out in Euclidean terms could ever displace the heap.
+// `true_distance` returns None for a candidate that's no longer present
+// (e.g. the pov car, already removed from other_cars by the time this
+// runs) -- such candidates are skipped rather than counted against `n`.
+fn nearest_n_by_tree<T: Copy>(
+    tree: &RTree<GeomWithData<[f32; 2], T>>,
+    query: [f32; 2],
+    n: usize,
+    mut true_distance: impl FnMut(T) -> Option<usize>,
+) -> Vec<T> {
+    if n == 0 {
+        return vec![];
+    }
+
+    struct Item<T> {
+        id: T,
+        distance: usize,
+    }
+
+    impl<T> PartialEq for Item<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.distance == other.distance
+        }
+    }
+
+    impl<T> Eq for Item<T> {}
+
+    impl<T> PartialOrd for Item<T> {
         fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            self.value.partial_cmp(&other.value)
+            Some(self.cmp(other))
         }
     }
 
-    impl<T, V> Ord for Item<T, V>
-    where
-        T: PartialEq + Eq,
-        V: Ord + PartialEq + Eq,
-    {
+    impl<T> Ord for Item<T> {
         fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.value.cmp(&other.value)
+            self.distance.cmp(&other.distance)
         }
     }
 
-    // tmp
-    // let iter = iter.collect::<Vec<_>>();
-    // // println!("Sorting lowest {n}: {iter:#?}");
-    // println!(
-    //     "vals: {:?}",
-    //     iter.iter()
-    //         .map(|i| format!("{:?}", val(i)))
-    //         .collect::<Vec<_>>()
-    // );
-    // let iter = iter.into_iter();
-
-    let mut heap: BinaryHeap<Item<I::Item, V>> = BinaryHeap::with_capacity(n + 1);
+    let mut heap: BinaryHeap<Item<T>> = BinaryHeap::with_capacity(n + 1);
 
-    let popped_elements_capacity = iter.size_hint().1.map_or(0, |c| c.saturating_sub(n));
-    let mut popped_elements = Vec::with_capacity(popped_elements_capacity);
+    for candidate in tree.nearest_neighbor_iter(&query) {
+        if heap.len() >= n {
+            let worst_distance = heap.peek().unwrap().distance;
+            if candidate.distance_2(&query) > (worst_distance as f32).powi(2) {
+                break;
+            }
+        }
 
-    for item in iter {
-        let item = Item {
-            value: val(&item),
-            item,
+        let Some(distance) = true_distance(candidate.data) else {
+            continue;
         };
-        heap.push(item);
 
+        heap.push(Item {
+            id: candidate.data,
+            distance,
+        });
         if heap.len() > n {
-            let popped = heap.pop();
-            popped_elements.push(popped.unwrap().item);
+            heap.pop();
         }
     }
 
-    let result: Vec<I::Item> = heap
-        .into_sorted_vec()
+    heap.into_sorted_vec()
         .into_iter()
-        .map(|item| item.item)
-        .chain(popped_elements)
-        .collect();
+        .map(|item| item.id)
+        .collect()
+}
 
-    // tmp
-    // println!("result: {result:#?}");
-    // println!(
-    //     "sorted vals: {:?}",
-    //     result
-    //         .iter()
-    //         .map(|i| format!("{:?}", val(i)))
-    //         .collect::<Vec<_>>()
-    // );
+// partitions `items` into the subset named by `nearest_ids` (sorted to
+// match that order), followed by everything else in no particular order --
+// the same contract lowest_n_sorted used to have: every item comes back,
+// just with the nearest `n` sorted at the front.
+fn reorder_by_ids<T, Id: PartialEq>(
+    mut items: Vec<T>,
+    nearest_ids: &[Id],
+    id_of: impl Fn(&T) -> Id,
+) -> Vec<T> {
+    let mut sorted = Vec::with_capacity(items.len());
+
+    for id in nearest_ids {
+        if let Some(index) = items.iter().position(|item| id_of(item) == *id) {
+            sorted.push(items.swap_remove(index));
+        }
+    }
 
-    result
+    sorted.extend(items);
+    sorted
+}
+
+// single-link agglomerative clustering of idle passengers, à la vrp-
+// pragmatic's job clustering: repeatedly seed a new cluster with the
+// unclustered passenger closest to the pov car, then absorb every
+// unclustered passenger within `cluster_radius` of *any* current member
+// (not just the seed), transitively, until no more can be absorbed. uses
+// the same distance_to metric with_pov's own sorting uses -- road distance,
+// not Euclidean -- so it can fall through to a full Path::find per pair;
+// fine for the passenger counts this observation deals with, but O(n^2) in
+// the worst case of zero clustering (every passenger its own cluster).
+fn cluster_idle_passengers(
+    passengers: &[PyPassenger],
+    pov_position: CarPosition,
+    cluster_radius: usize,
+) -> Vec<PyPassengerCluster> {
+    let mut remaining: Vec<&PyPassenger> = passengers.iter().collect();
+    let mut clusters = Vec::new();
+
+    while !remaining.is_empty() {
+        let seed_index = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, p)| pov_position.distance_to(CarPosition::from(p.pos)))
+            .map(|(index, _)| index)
+            .expect("remaining is non-empty");
+        let seed = remaining.swap_remove(seed_index);
+
+        let mut members = vec![seed];
+        let mut frontier = vec![seed];
+
+        while let Some(current) = frontier.pop() {
+            let current_pos = CarPosition::from(current.pos);
+
+            let mut index = 0;
+            while index < remaining.len() {
+                let candidate_pos = CarPosition::from(remaining[index].pos);
+                if current_pos.distance_to(candidate_pos) <= cluster_radius {
+                    let absorbed = remaining.swap_remove(index);
+                    frontier.push(absorbed);
+                    members.push(absorbed);
+                } else {
+                    index += 1;
+                }
+            }
+        }
+
+        clusters.push(build_passenger_cluster(members));
+    }
+
+    clusters
+}
+
+// folds a cluster's members into the PyPassengerCluster the agent sees:
+// aggregate stats, plus a centroid snapped to whichever member is actually
+// closest to the cluster's mean position (there's no guarantee the mean of
+// several RoadSections' checkerboard coords is itself a valid RoadSection).
+fn build_passenger_cluster(members: Vec<&PyPassenger>) -> PyPassengerCluster {
+    let count = members.len();
+
+    let mean_ticks_since_request = members
+        .iter()
+        .map(|p| p.ticks_since_request as f32)
+        .sum::<f32>()
+        / count as f32;
+    let mean_distance_to_destination = members
+        .iter()
+        .map(|p| p.distance_to_destination as f32)
+        .sum::<f32>()
+        / count as f32;
+
+    let positions: Vec<(f32, f32)> = members
+        .iter()
+        .map(|p| RoadSection::from(p.pos).checkerboard_coords())
+        .collect();
+    let mean_x = positions.iter().map(|(x, _)| x).sum::<f32>() / count as f32;
+    let mean_y = positions.iter().map(|(_, y)| y).sum::<f32>() / count as f32;
+
+    let centroid_index = positions
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            let dist_sq = |(x, y): &(f32, f32)| (x - mean_x).powi(2) + (y - mean_y).powi(2);
+            dist_sq(a).total_cmp(&dist_sq(b))
+        })
+        .map(|(index, _)| index)
+        .expect("members is non-empty");
+
+    PyPassengerCluster {
+        centroid: members[centroid_index].pos,
+        count,
+        mean_ticks_since_request,
+        mean_distance_to_destination,
+        member_ids: members.iter().map(|p| p.id).collect(),
+    }
 }
 
 impl PyPassenger {
-    pub fn idle(passenger: &Passenger, ticks_passed: usize) -> Self {
-        let path_to_destination = Path::find(passenger.start, passenger.destination);
-        let distance_to_destination = path_to_destination.cost;
+    pub fn idle(passenger: &Passenger, grid: &Grid, ticks_passed: usize) -> Self {
+        let distance_to_destination = grid.section_distance(
+            passenger.start.road_section,
+            passenger.destination.road_section,
+        );
 
         Self {
             id: passenger.id,
@@ -399,13 +897,27 @@ impl PyPassenger {
         }
     }
 
-    pub fn riding(passenger: &Passenger, car: &Car, ticks_passed: usize) -> Self {
-        let path_to_destination = Path::find(passenger.start, passenger.destination);
-        let distance_to_destination = path_to_destination.cost;
+    pub fn riding(passenger: &Passenger, car: &Car, grid: &Grid, ticks_passed: usize) -> Self {
+        Self::riding_at(passenger, car.position, grid, ticks_passed)
+    }
+
+    // like riding(), but for a passenger aboard something that isn't a Car
+    // (e.g. a rail::Train) -- takes the vehicle's current position directly
+    // rather than borrowing the whole vehicle.
+    pub fn riding_at(
+        passenger: &Passenger,
+        position: CarPosition,
+        grid: &Grid,
+        ticks_passed: usize,
+    ) -> Self {
+        let distance_to_destination = grid.section_distance(
+            passenger.start.road_section,
+            passenger.destination.road_section,
+        );
 
         Self {
             id: passenger.id,
-            pos: car.position.into(),
+            pos: position.into(),
             destination: passenger.destination.into(),
             state: PyPassengerState::Riding,
             ticks_since_request: ticks_passed - passenger.start_tick,
@@ -448,7 +960,7 @@ impl From<PyCoords> for CarPosition {
 }
 
 impl PyCar {
-    pub fn build(car: &Car, ticks_passed: usize) -> Self {
+    pub fn build(car: &Car, grid: &Grid, ticks_passed: usize) -> Self {
         let ty = match car.props.agent.is_npc() {
             true => PyCarType::Npc,
             false => PyCarType::Agent,
@@ -457,16 +969,22 @@ impl PyCar {
         // process passengers in car
         let mut passengers = Vec::with_capacity(car.passengers.len());
         for passenger in &car.passengers {
-            let CarPassenger::DroppingOff(passenger) = passenger else {
+            let CarPassenger::DroppingOff(passenger, _) = passenger else {
                 continue; // only process passengers currently in the car
             };
 
-            let py_passenger = PyPassenger::riding(passenger, car, ticks_passed);
+            let py_passenger = PyPassenger::riding(passenger, car, grid, ticks_passed);
             passengers.push(py_passenger);
         }
 
         let recent_actions = car.recent_actions.iter().copied().collect();
         let ticks_since_out_of_battery = car.ticks_since_out_of_battery;
+        let shuttle_status = car
+            .props
+            .agent
+            .as_path_agent()
+            .and_then(|agent| agent.shuttle_status())
+            .map(|status| (status.stop_index, status.at_stop));
 
         Self {
             id: car.id(),
@@ -477,17 +995,67 @@ impl PyCar {
             recent_actions,
             ticks_since_out_of_battery,
             active_action: car.active_action,
+            blocked_by_leader: car.blocked_by_leader,
+            length: car.props.length,
+            shuttle_status,
         }
     }
 }
 
+impl PyTrain {
+    pub fn build(train: &Train, line: &RailLine, grid: &Grid) -> Self {
+        let front = train.front_section(line);
+        let front_pos = station_car_position(front);
+
+        let occupied = train
+            .occupied_sections(line)
+            .into_iter()
+            .map(|section| station_car_position(section).into())
+            .collect();
+
+        let passengers = train
+            .passengers
+            .iter()
+            .map(|p| PyPassenger::riding_at(p, front_pos, grid, grid.ticks_passed))
+            .collect();
+
+        let line_stops = line
+            .stops
+            .iter()
+            .map(|stop| station_car_position(*stop).into())
+            .collect();
+
+        Self {
+            id: train.id,
+            front: front_pos.into(),
+            occupied,
+            capacity: train.capacity,
+            passengers,
+            at_stop: train.at_stop(),
+            line_stops,
+            line_loop_back: line.loop_back,
+        }
+    }
+}
+
+// a station RoadSection has no meaningful position_in_section of its own
+// (trains don't occupy cells within a section the way cars do), so this
+// just pins it to the section's start for PyCoords/RoadCoords purposes.
+fn station_car_position(section: RoadSection) -> CarPosition {
+    CarPosition {
+        road_section: section,
+        position_in_section: 0,
+        in_charging_station: None,
+    }
+}
+
 impl PyChargingStation {
     pub fn build(station: &ChargingStation, grid: &Grid) -> Self {
         let cars = station
             .cars
             .iter()
             .map(|car_id| grid.car(*car_id))
-            .map(|car| PyCar::build(car, grid.ticks_passed))
+            .map(|car| PyCar::build(car, grid, grid.ticks_passed))
             .collect::<Vec<_>>();
 
         Self {
@@ -496,6 +1064,8 @@ impl PyChargingStation {
             capacity: station.capacity,
             charging_speed: station.charging_speed.get(),
             cars,
+            queue_length: station.queue.len(),
+            estimated_wait_ticks: station.estimated_wait_ticks(),
         }
     }
 }
@@ -507,6 +1077,17 @@ impl PyTickEvents {
             car_picked_up_passenger: vec![],
             car_dropped_off_passenger: vec![],
             car_out_of_battery: vec![],
+            collisions: vec![],
+            transit_boarded: vec![],
+            transit_alighted: vec![],
+            car_queued_for_charging: vec![],
+            car_started_charging: vec![],
+            section_closed: vec![],
+            section_reopened: vec![],
+            passenger_start_unreachable: vec![],
+            intersection_reservation_granted: vec![],
+            intersection_reservation_denied: vec![],
+            passenger_abandoned: vec![],
         };
 
         for event in &grid.tick_events {
@@ -516,7 +1097,7 @@ impl PyTickEvents {
                         .get_idle_passenger(*passenger_id)
                         .expect("Passenger spawned but not found");
 
-                    let py_passenger = PyPassenger::idle(passenger, grid.ticks_passed);
+                    let py_passenger = PyPassenger::idle(passenger, grid, grid.ticks_passed);
                     let py_pos = py_passenger.pos;
                     this.passenger_spawned.push((py_passenger, py_pos));
                 }
@@ -527,15 +1108,15 @@ impl PyTickEvents {
                         .passengers
                         .iter()
                         .find_map(|p| {
-                            if let CarPassenger::DroppingOff(p) = p {
+                            if let CarPassenger::DroppingOff(p, _) = p {
                                 return (p.id == *passenger_id).then_some(p);
                             };
                             None
                         })
                         .expect("Passenger picked up but not found in car");
 
-                    let py_car = PyCar::build(car, grid.ticks_passed);
-                    let py_passenger = PyPassenger::riding(passenger, car, grid.ticks_passed);
+                    let py_car = PyCar::build(car, grid, grid.ticks_passed);
+                    let py_passenger = PyPassenger::riding(passenger, car, grid, grid.ticks_passed);
                     let py_pos = py_passenger.pos;
                     this.car_picked_up_passenger
                         .push((py_car, py_passenger, py_pos));
@@ -544,8 +1125,8 @@ impl PyTickEvents {
                 TickEvent::PassengerDroppedOff(car_id, passenger) => {
                     let car = grid.car(*car_id);
 
-                    let py_passenger = PyPassenger::riding(passenger, car, grid.ticks_passed);
-                    let py_car = PyCar::build(car, grid.ticks_passed);
+                    let py_passenger = PyPassenger::riding(passenger, car, grid, grid.ticks_passed);
+                    let py_car = PyCar::build(car, grid, grid.ticks_passed);
                     let py_pos = py_passenger.pos;
 
                     this.car_dropped_off_passenger
@@ -556,6 +1137,79 @@ impl PyTickEvents {
                     let py_pos = (*out_of_battery_pos).into();
                     this.car_out_of_battery.push((*car_id, py_pos));
                 }
+
+                TickEvent::Collision(trailing_car_id, leading_car_id, position) => {
+                    let py_pos = (*position).into();
+                    this.collisions
+                        .push((*trailing_car_id, *leading_car_id, py_pos));
+                }
+
+                TickEvent::TransitBoarded(train_id, passenger_id) => {
+                    let train = &grid.trains[train_id];
+                    let line = grid.rail_line(train.line);
+                    let front = train.front_section(line);
+
+                    let passenger = train
+                        .passengers
+                        .iter()
+                        .find(|p| p.id == *passenger_id)
+                        .expect("Passenger boarded but not found in train");
+
+                    let py_passenger = PyPassenger::riding_at(
+                        passenger,
+                        station_car_position(front),
+                        grid,
+                        grid.ticks_passed,
+                    );
+                    let py_pos = py_passenger.pos;
+                    this.transit_boarded.push((*train_id, py_passenger, py_pos));
+                }
+
+                TickEvent::TransitAlighted(train_id, passenger) => {
+                    let py_passenger = PyPassenger::riding_at(
+                        passenger,
+                        passenger.destination,
+                        grid,
+                        grid.ticks_passed,
+                    );
+                    let py_pos = py_passenger.pos;
+                    this.transit_alighted
+                        .push((*train_id, py_passenger, py_pos));
+                }
+
+                TickEvent::CarQueuedForCharging(car_id, cs_id) => {
+                    this.car_queued_for_charging.push((*car_id, *cs_id));
+                }
+
+                TickEvent::CarStartedCharging(car_id, cs_id) => {
+                    this.car_started_charging.push((*car_id, *cs_id));
+                }
+
+                TickEvent::SectionClosed(section) => {
+                    this.section_closed.push(*section);
+                }
+
+                TickEvent::SectionReopened(section) => {
+                    this.section_reopened.push(*section);
+                }
+
+                TickEvent::PassengerStartUnreachable(passenger_id) => {
+                    this.passenger_start_unreachable.push(*passenger_id);
+                }
+
+                TickEvent::IntersectionReservationGranted(car_id, intersection_id) => {
+                    this.intersection_reservation_granted
+                        .push((*car_id, *intersection_id));
+                }
+
+                TickEvent::IntersectionReservationDenied(car_id, intersection_id) => {
+                    this.intersection_reservation_denied
+                        .push((*car_id, *intersection_id));
+                }
+
+                TickEvent::PassengerAbandoned(passenger_id) => {
+                    this.passenger_abandoned.push(*passenger_id);
+                }
             }
         }
 
@@ -583,4 +1237,8 @@ impl PyChargingStation {
     fn is_full(&self) -> bool {
         self.cars.len() == self.capacity
     }
+
+    fn has_queue(&self) -> bool {
+        self.queue_length > 0
+    }
 }
@@ -6,7 +6,7 @@ use crate::logic::{
 
 use super::{
     grid::{GridRenderer, RoadRenderer},
-    util::Line,
+    util::{CurvePath, Line, Point},
 };
 
 pub struct CarRenderer<'g> {
@@ -18,13 +18,20 @@ impl<'g> CarRenderer<'g> {
     pub const ROAD_EDGE_MARGIN: f32 = 1.0;
     pub const BETWEEN_CARS_MARGIN: f32 = 1.0;
 
-    // whether we drive on the left side of the road
-    pub const ENGLAND_MODE: bool = true; // this should really be somewhere else...
-
     // const COLOUR: Color = RED;
     const HEADLIGHT_COLOUR: Color = YELLOW;
+    const BRAKE_LIGHT_COLOUR: Color = RED;
     const PATH_COLOUR: Color = GREEN;
 
+    // alpha applied to the path line once it runs past how far the car's
+    // remaining battery can actually take it (see Car::range_cells), so an
+    // about-to-strand car is visible at a glance without a second legend
+    const OUT_OF_RANGE_ALPHA: f32 = 0.25;
+
+    // how closely the flattened turn curve has to hug the true Bezier
+    // before CurvePath::flatten stops subdividing (see render_bounds)
+    const CURVE_FLATTEN_TOLERANCE: f32 = 2.0;
+
     pub fn new(car: &'g Car, grid_renderer: &'g GridRenderer<'g>) -> Self {
         Self { car, grid_renderer }
     }
@@ -59,7 +66,7 @@ impl<'g> CarRenderer<'g> {
             return;
         }
 
-        let rect = self.rect();
+        let rect = self.model_scaled_rect();
         draw_rectangle(rect.x, rect.y, rect.w, rect.h, self.car.props.colour);
 
         // draw headlights
@@ -96,22 +103,98 @@ impl<'g> CarRenderer<'g> {
         let radius = margin / 2.0;
         draw_circle(x1, y1, radius, Self::HEADLIGHT_COLOUR);
         draw_circle(x2, y2, radius, Self::HEADLIGHT_COLOUR);
+
+        // lit up red at the rear whenever the car is being held back by the
+        // one ahead of it (Grid::gap_to_leader), same spot as the
+        // headlights but on the opposite edge
+        if self.car.blocked_by_leader {
+            let (x1, y1, x2, y2) = match direction {
+                Direction::Up => (
+                    rect.left() + margin,
+                    rect.bottom() - margin,
+                    rect.right() - margin,
+                    rect.bottom() - margin,
+                ),
+                Direction::Down => (
+                    rect.left() + margin,
+                    rect.top() + margin,
+                    rect.right() - margin,
+                    rect.top() + margin,
+                ),
+                Direction::Left => (
+                    rect.right() - margin,
+                    rect.top() + margin,
+                    rect.right() - margin,
+                    rect.bottom() - margin,
+                ),
+                Direction::Right => (
+                    rect.left() + margin,
+                    rect.top() + margin,
+                    rect.left() + margin,
+                    rect.bottom() - margin,
+                ),
+            };
+
+            draw_circle(x1, y1, radius, Self::BRAKE_LIGHT_COLOUR);
+            draw_circle(x2, y2, radius, Self::BRAKE_LIGHT_COLOUR);
+        }
     }
 
     fn rect(&self) -> Rect {
-        Self::rect_from_position(self.car.position, &self.road())
+        Self::rect_from_position(
+            self.car.position,
+            &self.road(),
+            self.car.props.model.cell_length,
+        )
     }
 
-    pub fn rect_from_position(position: CarPosition, road: &RoadRenderer) -> Rect {
+    // the car's rect, widened (around its own center) to this car's model
+    // width -- length is already accounted for by rect_from_position packing
+    // `cell_length` slots (see CarModel::cell_length), so only width is a
+    // purely cosmetic scale here
+    fn model_scaled_rect(&self) -> Rect {
+        let mut rect = self.rect();
+        let model = &self.car.props.model;
+
+        let orientation = self.road().orientation;
+        let width_scale = model.render_width;
+        let (w_scale, h_scale) = match orientation {
+            Orientation::Horizontal => (1.0, width_scale),
+            Orientation::Vertical => (width_scale, 1.0),
+        };
+
+        let new_w = rect.w * w_scale;
+        let new_h = rect.h * h_scale;
+
+        rect.x -= (new_w - rect.w) / 2.0;
+        rect.y -= (new_h - rect.h) / 2.0;
+        rect.w = new_w;
+        rect.h = new_h;
+
+        rect
+    }
+
+    // `cell_length` is how many CarPosition cells this car's model occupies
+    // (see CarModel::cell_length / Car::occupied_cells) -- the rect spans
+    // that many slots, from the car's rear cell through its front cell at
+    // `position`, so multi-cell vehicles render (and pack) as a correct
+    // integer number of slots instead of a single stretched slot.
+    pub fn rect_from_position(
+        position: CarPosition,
+        road: &RoadRenderer,
+        cell_length: usize,
+    ) -> Rect {
         let orientation = road.orientation;
 
-        let mut section_position = position.position_in_section;
-        if !position.road_section.direction.towards_positive() {
-            let max_section_position =
-                RoadRenderer::cars_per_section().get(orientation) as usize - 1;
+        let max_section_position = RoadRenderer::cars_per_section().get(orientation) as usize - 1;
 
-            section_position = max_section_position - section_position;
-        }
+        let front = position.position_in_section;
+        let rear = front.saturating_sub(cell_length.saturating_sub(1));
+        let (low, high) = if position.road_section.direction.towards_positive() {
+            (rear, front)
+        } else {
+            (max_section_position - front, max_section_position - rear)
+        };
 
         let direction = position.road_section.direction;
         let section_rect =
@@ -120,26 +203,24 @@ impl<'g> CarRenderer<'g> {
         // start with section rect as base
         let mut car_rect = section_rect;
 
-        // adjust rect size
-        if orientation == Orientation::Horizontal {
-            car_rect.w = Self::car_length();
-            car_rect.h = Self::car_width();
-        } else {
-            car_rect.h = Self::car_length();
-            car_rect.w = Self::car_width();
-        }
-
-        // rect doesn't start at section start, it's somewhere along it
+        // distance from one slot's front to the next slot's front
         let distance_between_cars = (RoadRenderer::section_lengths()
             - Self::car_length() * RoadRenderer::cars_per_section())
             / (RoadRenderer::cars_per_section() - 1.0);
-        let distance_from_section_start =
-            section_position as f32 * (Self::car_length() + distance_between_cars);
+        let slot_pitch = (Self::car_length() + distance_between_cars).get(orientation);
+
+        let distance_from_section_start = low as f32 * slot_pitch;
+        let span_length = Self::car_length() + (high - low) as f32 * slot_pitch;
 
+        // adjust rect size
         if orientation == Orientation::Horizontal {
-            car_rect.x += distance_from_section_start.h;
+            car_rect.w = span_length;
+            car_rect.h = Self::car_width();
+            car_rect.x += distance_from_section_start;
         } else {
-            car_rect.y += distance_from_section_start.v;
+            car_rect.h = span_length;
+            car_rect.w = Self::car_width();
+            car_rect.y += distance_from_section_start;
         }
 
         car_rect
@@ -170,8 +251,17 @@ impl<'g> CarRenderer<'g> {
             Some(AgentAction::HeadTowards(_)) => BROWN,
         };
 
+        let range = self.car.range_cells();
+        let mut travelled = 0;
+
+        // collect the straight-line hop boundaries first (same as before),
+        // then hand them to render_bounds, which rounds off any genuine
+        // turns among them into smooth curves instead of drawing through
+        // them (see CurvePath)
+        let mut bounds = vec![PathLineBound::Car(self.car.position)];
+        let mut colours = Vec::new();
+
         let mut start = PathLineBound::Car(self.car.position);
-        // for path_section in sections {
         while let Some(path_section) = sections.next() {
             let end = match sections.peek() {
                 Some(next_section) => {
@@ -191,18 +281,83 @@ impl<'g> CarRenderer<'g> {
                 }
             }
 
-            self.render_path_line(start, end, path_colour);
+            let colour = if travelled >= range {
+                Self::dim(path_colour)
+            } else {
+                path_colour
+            };
+            bounds.push(end);
+            colours.push(colour);
 
+            travelled += Self::hop_cells(start, end, path_section.direction);
             start = end;
         }
+
+        self.render_bounds(&bounds, &colours);
+    }
+
+    // true if `bound` is a turn (the road changes orientation across it)
+    // rather than a straight continuation into the next section -- only
+    // turns get rounded off into a curve, see render_bounds
+    fn is_turn(bound: PathLineBound) -> bool {
+        matches!(bound, PathLineBound::SectionsIntersection((s1, s2))
+            if s1.direction.orientation() != s2.direction.orientation())
+    }
+
+    // draws straight segments between consecutive bounds, except across a
+    // genuine turn, where the two segments meeting at that corner are
+    // merged into one smooth CurvePath (P0/P1 the points either side of the
+    // corner, the corner itself as the control point) instead of two
+    // straight lines meeting at a right angle. the curve is drawn in the
+    // colour of the segment leading into the turn.
+    fn render_bounds(&self, bounds: &[PathLineBound], colours: &[Color]) {
+        let points: Vec<Point> = bounds
+            .iter()
+            .enumerate()
+            .map(|(i, bound)| self.get_line_xy(*bound, i == 0))
+            .collect();
+
+        let mut i = 0;
+        while i < colours.len() {
+            let is_turn = i + 1 < colours.len() && Self::is_turn(bounds[i + 1]);
+
+            if is_turn {
+                let curve = CurvePath::new(points[i], points[i + 1], points[i + 2]);
+                for pair in curve.flatten(Self::CURVE_FLATTEN_TOLERANCE).windows(2) {
+                    Line::from_points(pair[0], pair[1]).draw(colours[i]);
+                }
+                i += 2;
+            } else {
+                Line::from_points(points[i], points[i + 1]).draw(colours[i]);
+                i += 1;
+            }
+        }
     }
 
-    fn render_path_line(&self, start: PathLineBound, end: PathLineBound, colour: Color) {
-        let (x1, y1) = self.get_line_xy(start, true);
-        let (x2, y2) = self.get_line_xy(end, false);
+    // same colour as path_colour, but faded out -- used for the part of the
+    // path the car's remaining battery won't actually reach (see
+    // Car::range_cells and OUT_OF_RANGE_ALPHA)
+    fn dim(colour: Color) -> Color {
+        Color::new(colour.r, colour.g, colour.b, Self::OUT_OF_RANGE_ALPHA)
+    }
 
-        let line = Line { x1, y1, x2, y2 };
-        line.draw(colour);
+    // how many cells this hop of the path covers, for accumulating travel
+    // distance against the car's battery range (see render_path)
+    fn hop_cells(start: PathLineBound, end: PathLineBound, direction: Direction) -> usize {
+        match (start, end) {
+            (PathLineBound::Car(car_pos), PathLineBound::Car(dest_pos)) => dest_pos
+                .position_in_section
+                .saturating_sub(car_pos.position_in_section),
+            (PathLineBound::Car(car_pos), PathLineBound::SectionsIntersection(_)) => {
+                direction.max_position_in_section() - car_pos.position_in_section + 1
+            }
+            (PathLineBound::SectionsIntersection(_), PathLineBound::Car(dest_pos)) => {
+                dest_pos.position_in_section + 1
+            }
+            (PathLineBound::SectionsIntersection(_), PathLineBound::SectionsIntersection(_)) => {
+                direction.max_position_in_section() + 1
+            }
+        }
     }
 
     fn road(&self) -> RoadRenderer<'g> {
@@ -210,26 +365,20 @@ impl<'g> CarRenderer<'g> {
         self.grid_renderer.road_at(road_section)
     }
 
-    fn get_line_xy(&self, line_bound: PathLineBound, start: bool) -> (f32, f32) {
-        let x;
-        let y;
-
+    fn get_line_xy(&self, line_bound: PathLineBound, start: bool) -> Point {
         match line_bound {
             PathLineBound::Car(car_pos) => {
                 let road = self.grid_renderer.road_at(car_pos.road_section);
 
-                let car_rect = Self::rect_from_position(car_pos, &road);
+                let car_rect =
+                    Self::rect_from_position(car_pos, &road, self.car.props.model.cell_length);
                 let line_through_car = Line::through_rect_middle(car_rect, road.orientation);
 
                 let towards_positive = car_pos.road_section.direction.towards_positive();
                 if towards_positive && start {
-                    // return (line_through_car.x2, line_through_car.y2);
-                    x = line_through_car.x2;
-                    y = line_through_car.y2;
+                    Point::new(line_through_car.x2, line_through_car.y2)
                 } else {
-                    // return (line_through_car.x1, line_through_car.y1);
-                    x = line_through_car.x1;
-                    y = line_through_car.y1;
+                    Point::new(line_through_car.x1, line_through_car.y1)
                 }
             }
 
@@ -243,23 +392,24 @@ impl<'g> CarRenderer<'g> {
                 let line1 = Line::through_rect_middle(rect1, road1.orientation);
                 let line2 = Line::through_rect_middle(rect2, road2.orientation);
 
-                let intersection = line1.intersection(line2);
-
-                (x, y) = match intersection {
-                    Some((x, y)) => (x, y),
+                // these are two perpendicular (or straight-through) road
+                // centre-lines meeting at a section boundary, not two
+                // arbitrary finite segments -- we want where they'd cross if
+                // extended, not whether they overlap as drawn, so this wants
+                // intersection_unbounded rather than the bounded default.
+                match line1.intersection_unbounded(line2) {
+                    Some(point) => point,
                     None => {
                         // they are parallel, i.e. two sections in straight line
 
                         // note: if s1 is behind s2, this will cause the line
                         // to go all the way across both sections. which is fine
                         // since the next section will just re-draw the same line
-                        (line2.x1, line2.y1)
+                        Point::new(line2.x1, line2.y1)
                     }
-                };
+                }
             }
         }
-
-        (x, y)
     }
 
     fn render_passenger_count(&self) {
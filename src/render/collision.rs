@@ -0,0 +1,35 @@
+use macroquad::{color::*, shapes::draw_circle, text::draw_text};
+
+use crate::logic::grid_util::TickEvent;
+
+use super::{grid::GridRenderer, util::RoadCoords};
+
+// flashes a marker over any RoadSection slot two cars collided on this tick
+// (see TickEvent::Collision) -- gone again next frame once tick_events is
+// cleared for the next tick, the same way the event itself is.
+pub struct CollisionRenderer;
+
+impl CollisionRenderer {
+    const RADIUS: f32 = 12.0;
+    const COLOUR: Color = RED;
+
+    pub fn render(grid: &GridRenderer) {
+        for event in &grid.grid.tick_events {
+            let TickEvent::Collision(trailing_car_id, leading_car_id, position) = event else {
+                continue;
+            };
+
+            let center = RoadCoords::new(*position, grid).offset_coords(0.0);
+            draw_circle(center.x, center.y, Self::RADIUS, Self::COLOUR);
+
+            let text = format!("{trailing_car_id:?} x {leading_car_id:?}");
+            draw_text(
+                &text,
+                center.x - Self::RADIUS,
+                center.y - Self::RADIUS - 5.0,
+                14.0,
+                RED,
+            );
+        }
+    }
+}
@@ -23,12 +23,16 @@ impl ChargingStationRenderer {
         // ccar = charging car
         let mut ccar_rects = Vec::with_capacity(charging_station.capacity);
         for i in 0..charging_station.capacity {
-            let (cx, cy) = road_coords
+            let center = road_coords
                 .sidewalk_coords(Self::DISTANCE_FROM_ROAD + i as f32 * Self::SIDE_LENGTH);
 
             let hs = Self::SIDE_LENGTH / 2.0; // hs = half side
-            let (x1, y1) = (cx - hs, cy - hs);
-            let rect = Rect::new(x1, y1, Self::SIDE_LENGTH, Self::SIDE_LENGTH);
+            let rect = Rect::new(
+                center.x - hs,
+                center.y - hs,
+                Self::SIDE_LENGTH,
+                Self::SIDE_LENGTH,
+            );
             ccar_rects.push(rect);
         }
 
@@ -47,6 +51,21 @@ impl ChargingStationRenderer {
         // draw charging station rect as green outline
         draw_rectangle_lines(x1, y1, x2 - x1, y2 - y1, 3.0, LIME);
 
+        // occupied/capacity, plus queued count if anyone's waiting
+        let occupancy_text = match charging_station.queue.len() {
+            0 => format!(
+                "{}/{}",
+                charging_station.cars.len(),
+                charging_station.capacity
+            ),
+            queued => format!(
+                "{}/{} (+{queued})",
+                charging_station.cars.len(),
+                charging_station.capacity
+            ),
+        };
+        draw_text(&occupancy_text, x1, y1 - 5.0, 14.0, WHITE);
+
         // draw charging cars
         for (car_id, rect) in charging_station.cars.iter().zip(ccar_rects.iter()) {
             let car = grid.grid.car(*car_id);
@@ -1,14 +1,17 @@
 use macroquad::prelude::*;
 
 use crate::logic::{
+    elevation,
     grid::{Grid, LightState},
-    util::{Direction, Orientation, RoadSection},
+    util::{Direction, DrivingSide, Orientation, RoadSection, RoadType},
 };
 
 use super::{
     car::CarRenderer,
+    collision::CollisionRenderer,
     ev::ChargingStationRenderer,
     passenger::PassengerRenderer,
+    rail::TrainRenderer,
     util::{Lengths, ToLengths},
 };
 
@@ -49,6 +52,13 @@ impl<'g> GridRenderer<'g> {
         for charging_station in self.grid.charging_stations.values() {
             ChargingStationRenderer::render(self, charging_station);
         }
+
+        for train in self.grid.trains() {
+            let line = self.grid.rail_line(train.line);
+            TrainRenderer::render(self, line, train);
+        }
+
+        CollisionRenderer::render(self);
     }
 
     fn roads(grid: &'g Grid) -> Vec<RoadRenderer> {
@@ -197,43 +207,60 @@ impl<'g> RoadRenderer<'g> {
         }
     }
 
+    pub fn road_type(&self) -> RoadType {
+        RoadType::for_road(self.orientation, self.index)
+    }
+
     fn lane_dividers(&self) -> Vec<Rect> {
         let mut rects = Vec::new();
 
+        let road_type = self.road_type();
+
+        // a one-way road has no opposing traffic to separate, so it only
+        // needs dividers between its own lanes (none at all for the common
+        // single-lane case)
+        let lane_boundary_count = road_type.lanes.saturating_sub(1);
+
         // for horizontal roads:
         // fixed_coord is y (it's the same for all stripes)
         // variable_coord is x (it's different for each stripe)
-        let fixed_coord = match self.orientation {
+        let road_fixed_coord = match self.orientation {
             Orientation::Horizontal => self.rect.y,
             Orientation::Vertical => self.rect.x,
-        } + RoadRenderer::WIDTH / 2.;
+        };
 
-        let mut variable_coord = GridRenderer::MARGIN + Self::LANE_DIVIDER_SPACING;
         let max_variable_coord = match self.orientation {
             Orientation::Horizontal => screen_width(),
             Orientation::Vertical => screen_height(),
         } - GridRenderer::MARGIN;
 
-        while variable_coord < max_variable_coord {
-            let (x, y, w, h) = match self.orientation {
-                Orientation::Horizontal => (
-                    variable_coord,
-                    fixed_coord,
-                    Self::LANE_DIVIDER_LENGTH,
-                    Self::LANE_DIVIDER_THICKNESS,
-                ),
-                Orientation::Vertical => (
-                    fixed_coord,
-                    variable_coord,
-                    Self::LANE_DIVIDER_THICKNESS,
-                    Self::LANE_DIVIDER_LENGTH,
-                ),
-            };
-
-            let rect = Rect::new(x, y, w, h);
-            rects.push(rect);
-
-            variable_coord += Self::LANE_DIVIDER_LENGTH + Self::LANE_DIVIDER_SPACING;
+        for boundary in 1..=lane_boundary_count {
+            let fixed_coord = road_fixed_coord
+                + RoadRenderer::WIDTH * boundary as f32 / road_type.lanes as f32;
+
+            let mut variable_coord = GridRenderer::MARGIN + Self::LANE_DIVIDER_SPACING;
+
+            while variable_coord < max_variable_coord {
+                let (x, y, w, h) = match self.orientation {
+                    Orientation::Horizontal => (
+                        variable_coord,
+                        fixed_coord,
+                        Self::LANE_DIVIDER_LENGTH,
+                        Self::LANE_DIVIDER_THICKNESS,
+                    ),
+                    Orientation::Vertical => (
+                        fixed_coord,
+                        variable_coord,
+                        Self::LANE_DIVIDER_THICKNESS,
+                        Self::LANE_DIVIDER_LENGTH,
+                    ),
+                };
+
+                let rect = Rect::new(x, y, w, h);
+                rects.push(rect);
+
+                variable_coord += Self::LANE_DIVIDER_LENGTH + Self::LANE_DIVIDER_SPACING;
+            }
         }
 
         rects
@@ -247,6 +274,15 @@ impl<'g> RoadRenderer<'g> {
             self.rect.h,
             Self::COLOUR,
         );
+
+        for (rect, colour) in self.elevation_shades() {
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, colour);
+        }
+
+        for (rect, colour) in self.congestion_tints() {
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, colour);
+        }
+
         draw_rectangle_lines(
             self.rect.x,
             self.rect.y,
@@ -267,6 +303,75 @@ impl<'g> RoadRenderer<'g> {
         }
     }
 
+    // shade each section by its terrain height, darker = lower, so the
+    // gradient cars are climbing/descending is visible at a glance
+    fn elevation_shades(&self) -> Vec<(Rect, Color)> {
+        // elevation doesn't depend on which direction's lane we pick, just
+        // on where the section physically is
+        let canonical_direction = self.orientation.direction(true);
+
+        let mut shades = Vec::new();
+        for section_index in 0..=self.orientation.max_section_index() as isize {
+            let section = RoadSection::get_raw(canonical_direction, self.index, section_index);
+            if section.valid().is_err() {
+                continue;
+            }
+
+            let rect = self.section_rect(section_index);
+            shades.push((rect, Self::elevation_colour(section.elevation())));
+        }
+
+        shades
+    }
+
+    fn elevation_colour(elevation: f32) -> Color {
+        const MIN_BRIGHTNESS: f32 = 0.5;
+
+        let t = (elevation / elevation::HEIGHT_SCALE).clamp(0.0, 1.0);
+        let brightness = MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * t;
+
+        Color::new(
+            Self::COLOUR.r * brightness,
+            Self::COLOUR.g * brightness,
+            Self::COLOUR.b * brightness,
+            1.0,
+        )
+    }
+
+    // each occupied section/side is tinted green->red by how congested it
+    // is, so jams are visible at a glance
+    fn congestion_tints(&self) -> Vec<(Rect, Color)> {
+        let directions = match self.orientation {
+            Orientation::Horizontal => [Direction::Left, Direction::Right],
+            Orientation::Vertical => [Direction::Up, Direction::Down],
+        };
+
+        let mut tints = Vec::new();
+        for section_index in 0..=self.orientation.max_section_index() as isize {
+            for direction in directions {
+                let section = RoadSection::get_raw(direction, self.index, section_index);
+                if section.valid().is_err() {
+                    continue;
+                }
+
+                let congestion = self.grid.congestion_at(&section);
+                if congestion.occupancy <= 0.0 {
+                    continue;
+                }
+
+                let rect = self.section_rect_on_side(section_index, direction);
+                tints.push((rect, Self::congestion_colour(congestion.occupancy)));
+            }
+        }
+
+        tints
+    }
+
+    fn congestion_colour(occupancy: f32) -> Color {
+        let t = occupancy.clamp(0.0, 1.0);
+        Color::new(t, 1.0 - t, 0.0, 1.0)
+    }
+
     pub fn road_counts() -> Lengths {
         Lengths {
             h: Grid::HORIZONTAL_ROADS as f32,
@@ -314,12 +419,12 @@ impl<'g> RoadRenderer<'g> {
         rect
     }
 
-    pub fn on_positive_side_of_road(direction: Direction) -> bool {
+    pub fn on_positive_side_of_road(direction: Direction, driving_side: DrivingSide) -> bool {
         // the "positive" side is the road lane furthest from 0, 0
         // 0, 0 is top left
 
         let mut positive = direction == Direction::Down || direction == Direction::Left;
-        if !CarRenderer::ENGLAND_MODE {
+        if !driving_side.is_left() {
             positive = !positive;
         }
 
@@ -329,7 +434,13 @@ impl<'g> RoadRenderer<'g> {
     pub fn section_rect_on_side(&self, section_index: isize, direction: Direction) -> Rect {
         let mut section_rect = self.section_rect(section_index);
 
-        let positive_side = Self::on_positive_side_of_road(direction);
+        // a one-way road has a single flow of traffic occupying its whole
+        // width, not two opposing sides
+        if self.road_type().one_way.is_some() {
+            return section_rect;
+        }
+
+        let positive_side = Self::on_positive_side_of_road(direction, self.grid.opts.driving_side);
         match self.orientation {
             Orientation::Horizontal => {
                 section_rect.h /= 2.0;
@@ -376,6 +487,8 @@ impl<'g> TrafficLightsRenderer<'g> {
         // go through the empty spaces between the roads
         // and draw 4 traffic lights each time
 
+        let driving_side = self.grid_renderer.grid.opts.driving_side;
+
         for x in -1..Grid::VERTICAL_ROADS as isize {
             for y in -1..Grid::HORIZONTAL_ROADS as isize {
                 // the road section to the left of the blank space
@@ -403,17 +516,17 @@ impl<'g> TrafficLightsRenderer<'g> {
 
                 let mut back_offset = Self::BACK_MARGIN + Self::SQUARE_SIZE / 2.0;
                 let mut side_offset = Self::SIDE_MARGIN + Self::SQUARE_SIZE / 2.0;
-                if !CarRenderer::ENGLAND_MODE {
+                if !driving_side.is_left() {
                     (side_offset, back_offset) = (back_offset, side_offset);
                 }
 
-                // let topleft_direction = match CarRenderer::ENGLAND_MODE {
+                // let topleft_direction = match DrivingSide::CURRENT.is_left() {
                 //     true => Direction::Right,
                 //     false => Direction::Down,
                 // };
 
-                // function to invert direction if not in england
-                let i = |d: Direction| match CarRenderer::ENGLAND_MODE {
+                // function to invert direction if not driving on the left
+                let i = |d: Direction| match driving_side.is_left() {
                     true => d,
                     false => d.clockwise().clockwise(),
                 };
@@ -453,7 +566,7 @@ impl<'g> TrafficLightsRenderer<'g> {
                     if let Some(left) = left {
                         // top-left
                         self.render_light(
-                            match CarRenderer::ENGLAND_MODE {
+                            match driving_side.is_left() {
                                 true => top,
                                 false => left,
                             },
@@ -463,7 +576,7 @@ impl<'g> TrafficLightsRenderer<'g> {
                     }
                     // top-right
                     self.render_light(
-                        match CarRenderer::ENGLAND_MODE {
+                        match driving_side.is_left() {
                             true => right,
                             false => top,
                         },
@@ -473,7 +586,7 @@ impl<'g> TrafficLightsRenderer<'g> {
                 }
                 // bottom-right
                 self.render_light(
-                    match CarRenderer::ENGLAND_MODE {
+                    match driving_side.is_left() {
                         true => bottom,
                         false => right,
                     },
@@ -483,7 +596,7 @@ impl<'g> TrafficLightsRenderer<'g> {
                 if let Some(left) = left {
                     // bottom-left
                     self.render_light(
-                        match CarRenderer::ENGLAND_MODE {
+                        match driving_side.is_left() {
                             true => left,
                             false => bottom,
                         },
@@ -57,8 +57,8 @@ impl PassengerRenderer {
         };*/
 
         let road_coords = RoadCoords::new(position, grid);
-        let (cx, cy) = road_coords.sidewalk_coords(Self::DISTANCE_FROM_ROAD);
+        let center = road_coords.sidewalk_coords(Self::DISTANCE_FROM_ROAD);
 
-        draw_circle(cx, cy, Self::RADIUS, passenger.colour);
+        draw_circle(center.x, center.y, Self::RADIUS, passenger.colour);
     }
 }
@@ -0,0 +1,57 @@
+use macroquad::{color::*, shapes::draw_rectangle, text::draw_text};
+
+use crate::logic::{
+    car::CarPosition,
+    rail::{RailLine, Train},
+};
+
+use super::{grid::GridRenderer, util::RoadCoords};
+
+// draws a train as one small square per occupied RoadSection (see
+// Train::occupied_sections) rather than a single precisely-scaled rect the
+// way CarRenderer does for cars -- trains don't share a RoadSection's
+// per-car lane slots with anything else, so there's no need for that
+// geometry here.
+pub struct TrainRenderer;
+
+impl TrainRenderer {
+    const SIDE_LENGTH: f32 = 18.0;
+    const COLOUR: Color = PURPLE;
+
+    pub fn render(grid: &GridRenderer, line: &RailLine, train: &Train) {
+        let hs = Self::SIDE_LENGTH / 2.0; // hs = half side
+
+        for section in train.occupied_sections(line) {
+            let position = CarPosition {
+                road_section: section,
+                position_in_section: 0,
+                in_charging_station: None,
+            };
+            let road_coords = RoadCoords::new(position, grid);
+            let center = road_coords.sidewalk_coords(0.0);
+
+            draw_rectangle(
+                center.x - hs,
+                center.y - hs,
+                Self::SIDE_LENGTH,
+                Self::SIDE_LENGTH,
+                Self::COLOUR,
+            );
+        }
+
+        let front = CarPosition {
+            road_section: train.front_section(line),
+            position_in_section: 0,
+            in_charging_station: None,
+        };
+        let front_center = RoadCoords::new(front, grid).sidewalk_coords(0.0);
+        let text = format!("{}/{}", train.passengers.len(), train.capacity);
+        draw_text(
+            &text,
+            front_center.x - hs,
+            front_center.y - hs - 5.0,
+            14.0,
+            BLACK,
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     borrow::{Borrow, BorrowMut},
     ops::{Deref, DerefMut},
+    path::PathBuf,
     sync::{Arc, Condvar, Mutex, OnceLock},
     time::{Duration, Instant},
 };
@@ -10,58 +11,147 @@ use crate::logic::grid::Grid;
 
 use macroquad::prelude::*;
 
+// opt-in, set via GridLock::with_recording -- dumps every frame render_main
+// draws for this grid as a numbered PNG, so a headless training run can
+// produce a clip of a specific episode for debugging policy behaviour
+// afterwards, without a human watching the window live. PNGs rather than an
+// animated format since this crate has no video/gif encoder dependency to
+// reach for (see repo root: no Cargo.toml to add one to).
+#[derive(Clone)]
+struct Recording {
+    output_dir: PathBuf,
+    next_frame: Arc<Mutex<usize>>,
+}
+
+impl Recording {
+    fn capture(&self) {
+        let mut next_frame = self.next_frame.lock().unwrap();
+
+        let path = self
+            .output_dir
+            .join(format!("frame_{:06}.png", *next_frame));
+        get_screen_data().export_png(
+            path.to_str()
+                .expect("recording output_dir must be valid UTF-8"),
+        );
+
+        *next_frame += 1;
+    }
+}
+
 // bridge from the grid engine
 #[derive(Clone)]
 pub struct GridLock {
     mutex: Arc<Mutex<Grid>>,
+    recording: Option<Recording>,
 }
 
 impl GridLock {
     pub fn new(grid: Grid) -> Self {
         Self {
             mutex: Arc::new(Mutex::new(grid)),
+            recording: None,
         }
     }
 
+    // see Recording's doc comment. output_dir is created if it doesn't
+    // already exist.
+    pub fn with_recording(mut self, output_dir: PathBuf) -> Self {
+        std::fs::create_dir_all(&output_dir).expect("failed to create recording output_dir");
+        self.recording = Some(Recording {
+            output_dir,
+            next_frame: Arc::new(Mutex::new(0)),
+        });
+        self
+    }
+
     pub fn lock(&self) -> impl DerefMut<Target = Grid> + '_ {
         self.mutex.lock().unwrap()
     }
 }
 
+// keyed by the id new_grid hands back, so several PyGridEnvs can each have
+// render=true at once without one clobbering another's entry (previously
+// this held a single `current_grid: Option<GridLock>`, so a second env
+// simply replaced the first). `selected` is which registered grid the render
+// thread actually draws -- see new_grid's doc comment for why this is one
+// full-window env rather than a tiled montage of all of them.
 #[derive(Clone)]
 struct GridRenderGlobalState {
     version: usize,
-    current_grid: Option<GridLock>,
+    grids: std::collections::HashMap<usize, GridLock>,
+    next_id: usize,
+    selected: Option<usize>,
 }
 
 impl GridRenderGlobalState {
-    const fn default() -> Self {
+    fn new() -> Self {
         Self {
             version: 0,
-            current_grid: None,
+            grids: std::collections::HashMap::new(),
+            next_id: 0,
+            selected: None,
         }
     }
 }
 
-static GRID_STATE: Mutex<GridRenderGlobalState> = Mutex::new(GridRenderGlobalState::default());
+// HashMap::new() isn't const, so this can't be a plain `static ... = Mutex::
+// new(...)` the way the single-grid version used to be -- lazily built on
+// first access instead (same OnceLock-wrapped-lock shape as
+// logic::util::DrivingSide's process-wide fallback).
+static GRID_STATE: OnceLock<Mutex<GridRenderGlobalState>> = OnceLock::new();
+
+fn grid_state() -> &'static Mutex<GridRenderGlobalState> {
+    GRID_STATE.get_or_init(|| Mutex::new(GridRenderGlobalState::new()))
+}
 
-pub fn new_grid(grid_bridge: GridLock) {
+// registers a grid for rendering and returns its registry id; pass that id
+// to remove_grid on teardown (see PyGridEnv's Drop impl) so each env's
+// render=true lifecycle only ever touches its own entry.
+//
+// the render thread always draws exactly one selected grid, full window --
+// it does NOT tile every registered grid into a montage. accurately tiling
+// would mean threading a viewport rect through every section/road
+// coordinate computation in render/grid.rs (GridRenderer::grid_dimensions,
+// section_rect, ...), which is out of proportion to what actually unblocks
+// vectorized training: most parallel envs run with render=false, and only
+// the one(s) a human wants to watch need render=true at all.
+pub fn new_grid(grid_bridge: GridLock) -> usize {
     // macroquad's main() can't take any arguments.
     // so we sneak the game in through the back door.
 
     // note that this function should be able to be called
     // multiple times, but only call macroquad's main() once
 
-    let mut grid_state = GRID_STATE.lock().unwrap();
-    let is_first_time = grid_state.current_grid.is_none();
+    let mut grid_state = grid_state().lock().unwrap();
+    let is_first_time = grid_state.grids.is_empty();
 
-    grid_state.current_grid = Some(grid_bridge);
+    let id = grid_state.next_id;
+    grid_state.next_id += 1;
+    grid_state.grids.insert(id, grid_bridge);
+    grid_state.selected.get_or_insert(id);
     grid_state.version += 1;
     drop(grid_state);
 
     if is_first_time {
         std::thread::spawn(main);
     }
+
+    id
+}
+
+// deregisters a grid so the render thread stops drawing it -- called from
+// PyGridEnv::drop so a torn-down env's entry doesn't linger (or, if it was
+// the selected one, keep getting redrawn from a GridLock nothing updates
+// anymore).
+pub fn remove_grid(id: usize) {
+    let mut grid_state = grid_state().lock().unwrap();
+
+    grid_state.grids.remove(&id);
+    if grid_state.selected == Some(id) {
+        grid_state.selected = grid_state.grids.keys().next().copied();
+    }
+    grid_state.version += 1;
 }
 
 #[macroquad::main(window_conf)]
@@ -70,30 +160,29 @@ async fn main() {
 }
 
 async fn render_main() {
-    // new iteration for every new grid environment
+    // new iteration whenever the selected grid (or the registry) changes
     loop {
         let grid_ref = {
-            let grid_state = GRID_STATE.lock().unwrap();
+            let grid_state = grid_state().lock().unwrap();
             grid_state.clone()
         };
 
-        // for every tick in the current grid
+        // for every tick of the selected grid
         loop {
 
             // check we have latest grid
             {
                 let our_version = grid_ref.version;
-                let latest_version = {
-                    let grid_state = GRID_STATE.lock().unwrap();
-                    grid_state.version
-                };
+                let latest_version = grid_state().lock().unwrap().version;
                 if our_version != latest_version {
                     break;
                 }
             }
 
-            {
-                let grid = grid_ref.current_grid.as_ref().unwrap().lock();
+            // nothing registered (yet, or everything's been torn down) --
+            // just wait for new_grid to bump the version above
+            if let Some(grid_lock) = grid_ref.selected.and_then(|id| grid_ref.grids.get(&id)) {
+                let grid = grid_lock.lock();
 
                 // if grid.done() {
                 //     return;
@@ -101,6 +190,12 @@ async fn render_main() {
 
                 let renderer = GridRenderer::new(&grid);
                 renderer.render();
+
+                drop(grid);
+
+                if let Some(recording) = &grid_lock.recording {
+                    recording.capture();
+                }
             }
 
             // if last_tick.elapsed() >= time_per_tick {
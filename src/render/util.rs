@@ -1,4 +1,4 @@
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 use macroquad::prelude::*;
 
@@ -12,6 +12,134 @@ use crate::{
 
 use super::{car::CarRenderer, grid::GridRenderer};
 
+// Vec2-style 2D point, for geometry that's a single (x, y) position rather
+// than a Lengths-style pair of independent horizontal/vertical extents --
+// replaces bare (f32, f32) tuples across the renderer (see RoadCoords).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn normalized(self) -> Self {
+        self / self.length()
+    }
+
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            x: self.x.mul_add(1.0 - t, other.x * t),
+            y: self.y.mul_add(1.0 - t, other.y * t),
+        }
+    }
+
+    pub fn to_angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+}
+
+// heading in radians, measured the same way atan2/Point::to_angle does --
+// 0 points along +x (Right), increasing clockwise on screen since screen
+// space has +y down (Right -> Down -> Left -> Up). lets Line::through_rect_middle
+// and RoadCoords's sidewalk offset work for any heading, not just the two
+// cardinal Orientations, while From<Orientation> keeps the existing
+// Horizontal/Vertical call sites compiling unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Angle(pub f32);
+
+impl Angle {
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self(degrees.to_radians())
+    }
+
+    pub fn to_degrees(self) -> f32 {
+        self.0.to_degrees()
+    }
+
+    pub fn sin_cos(self) -> (f32, f32) {
+        self.0.sin_cos()
+    }
+
+    pub fn unit_vec(self) -> Point {
+        let (sin, cos) = self.sin_cos();
+        Point::new(cos, sin)
+    }
+}
+
+impl From<Orientation> for Angle {
+    fn from(orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Horizontal => Angle(0.0),
+            Orientation::Vertical => Angle(std::f32::consts::FRAC_PI_2),
+        }
+    }
+}
+
+// generates Add/Sub/Mul/Div (Point op Point, componentwise, and Point op
+// f32, scalar) for Point -- the "full operator set" a Vec2-style type needs,
+// without hand-writing 4 near-identical impls.
+macro_rules! impl_point_binop {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Point {
+            type Output = Point;
+
+            fn $method(self, rhs: Point) -> Point {
+                Point {
+                    x: self.x $op rhs.x,
+                    y: self.y $op rhs.y,
+                }
+            }
+        }
+
+        impl $trait<f32> for Point {
+            type Output = Point;
+
+            fn $method(self, rhs: f32) -> Point {
+                Point {
+                    x: self.x $op rhs,
+                    y: self.y $op rhs,
+                }
+            }
+        }
+    };
+}
+
+impl_point_binop!(Add, add, +);
+impl_point_binop!(Sub, sub, -);
+impl_point_binop!(Mul, mul, *);
+impl_point_binop!(Div, div, /);
+
+macro_rules! impl_point_assign_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Point {
+            fn $method(&mut self, rhs: Point) {
+                self.x $op rhs.x;
+                self.y $op rhs.y;
+            }
+        }
+
+        impl $trait<f32> for Point {
+            fn $method(&mut self, rhs: f32) {
+                self.x $op rhs;
+                self.y $op rhs;
+            }
+        }
+    };
+}
+
+impl_point_assign_op!(AddAssign, add_assign, +=);
+impl_point_assign_op!(SubAssign, sub_assign, -=);
+impl_point_assign_op!(MulAssign, mul_assign, *=);
+impl_point_assign_op!(DivAssign, div_assign, /=);
+
 // util struct for abstracting over whether we are in horizontal or vertical
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Lengths {
@@ -20,10 +148,10 @@ pub struct Lengths {
 }
 
 impl Lengths {
-    pub fn from_vec2(vec2: macroquad::math::Vec2) -> Self {
+    pub fn from_vec2(point: Point) -> Self {
         Self {
-            h: vec2.y,
-            v: vec2.x,
+            h: point.y,
+            v: point.x,
         }
     }
 
@@ -201,6 +329,10 @@ impl Line {
         Self { x1, y1, x2, y2 }
     }
 
+    pub fn from_points(a: Point, b: Point) -> Self {
+        Self::new(a.x, a.y, b.x, b.y)
+    }
+
     pub fn draw(self, colour: Color) {
         draw_line(self.x1, self.y1, self.x2, self.y2, 1.0, colour);
     }
@@ -210,89 +342,321 @@ impl Line {
         std::mem::swap(&mut self.y1, &mut self.y2);
     }
 
-    pub fn through_rect_middle(rect: Rect, orientation: Orientation) -> Self {
-        match orientation {
-            Orientation::Horizontal => {
-                let y = rect.top() + rect.h / 2.0;
-                Self {
-                    x1: rect.left(),
-                    y1: y,
-                    x2: rect.right(),
-                    y2: y,
-                }
-            }
-            Orientation::Vertical => {
-                let x = rect.left() + rect.w / 2.0;
-                Self {
-                    x1: x,
-                    y1: rect.top(),
-                    x2: x,
-                    y2: rect.bottom(),
-                }
+    // the line through rect's center at `angle`, clipped to rect's bounds.
+    // accepts anything Into<Angle> so the existing Orientation call sites
+    // (the cardinal fast paths) keep compiling unchanged.
+    pub fn through_rect_middle(rect: Rect, angle: impl Into<Angle>) -> Self {
+        let center = Point::new(rect.left() + rect.w / 2.0, rect.top() + rect.h / 2.0);
+        let dir = angle.into().unit_vec();
+
+        // distance along `dir` from the center to the nearest pair of
+        // edges it would hit first -- for a cardinal angle this is exactly
+        // the old left/right or top/bottom edge-to-edge line
+        let half_w = rect.w / 2.0;
+        let half_h = rect.h / 2.0;
+        let t_x = if dir.x != 0.0 {
+            half_w / dir.x.abs()
+        } else {
+            f32::INFINITY
+        };
+        let t_y = if dir.y != 0.0 {
+            half_h / dir.y.abs()
+        } else {
+            f32::INFINITY
+        };
+        let t = t_x.min(t_y);
+
+        Self::from_points(center - dir * t, center + dir * t)
+    }
+
+    // default tolerance for treating the denominator in `intersection` as
+    // zero (i.e. the two segments as parallel) -- see intersection_with_epsilon
+    // if a caller needs this to be looser/tighter (e.g. for near-parallel
+    // road lines that would otherwise produce wildly distant bogus points).
+    pub const DEFAULT_EPSILON: f32 = 1e-6;
+
+    // bounded segment intersection: only reports a point that actually lies
+    // on both segments (t, u in [0, 1]), unlike the old behaviour of treating
+    // both lines as infinite. Use intersection_unbounded for that.
+    pub fn intersection(self, other: Line) -> Option<Point> {
+        self.intersection_with_epsilon(other, Self::DEFAULT_EPSILON)
+    }
+
+    pub fn intersection_with_epsilon(self, other: Line, epsilon: f32) -> Option<Point> {
+        let d1 = Point::new(self.x2 - self.x1, self.y2 - self.y1);
+        let d2 = Point::new(other.x2 - other.x1, other.y2 - other.y1);
+        let denom = d1.x * d2.y - d1.y * d2.x;
+
+        let to_other = Point::new(other.x1 - self.x1, other.y1 - self.y1);
+
+        if denom.abs() < epsilon {
+            // parallel -- only worth reporting anything if the two segments
+            // are also collinear (this cross product is proportional to the
+            // distance between the two infinite lines)
+            let cross = to_other.x * d1.y - to_other.y * d1.x;
+            if cross.abs() >= epsilon {
+                return None;
             }
+
+            return self.collinear_overlap_midpoint(other, d1);
+        }
+
+        let t = (to_other.x * d2.y - to_other.y * d2.x) / denom;
+        let u = (to_other.x * d1.y - to_other.y * d1.x) / denom;
+
+        if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+            return None; // lines cross, but only if extended past their segments
         }
+
+        Some(Point::new(self.x1 + t * d1.x, self.y1 + t * d1.y))
     }
 
-    pub fn intersection(self, other: Line) -> Option<(f32, f32)> {
-        // https://en.wikipedia.org/wiki/Line%E2%80%93line_intersection#Given_two_points_on_each_line
-        let (x1, y1, x2, y2) = (self.x1, self.y1, self.x2, self.y2);
-        let (x3, y3, x4, y4) = (other.x1, other.y1, other.x2, other.y2);
+    // self and other are collinear (see intersection_with_epsilon) -- find
+    // where they overlap along self's own direction and return its midpoint,
+    // or None if they don't overlap at all.
+    fn collinear_overlap_midpoint(self, other: Line, d1: Point) -> Option<Point> {
+        let len_sq = d1.x * d1.x + d1.y * d1.y;
+        let project = |x: f32, y: f32| ((x - self.x1) * d1.x + (y - self.y1) * d1.y) / len_sq;
+
+        let other_t1 = project(other.x1, other.y1);
+        let other_t2 = project(other.x2, other.y2);
+        let (other_lo, other_hi) = (other_t1.min(other_t2), other_t1.max(other_t2));
+
+        let lo = 0.0_f32.max(other_lo);
+        let hi = 1.0_f32.min(other_hi);
+        if lo > hi {
+            return None; // collinear, but don't actually overlap
+        }
+
+        let mid = (lo + hi) / 2.0;
+        Some(Point::new(self.x1 + mid * d1.x, self.y1 + mid * d1.y))
+    }
 
-        let x_denom = (x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4);
-        let y_denom = (x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4);
-        if x_denom == 0.0 || y_denom == 0.0 {
+    // the old infinite-line crossing, with no segment-bounds check at all --
+    // still what section-corner lookups want, since through_rect_middle lines
+    // only span their own rect and won't generally reach as far as the
+    // neighbouring section's corner.
+    pub fn intersection_unbounded(self, other: Line) -> Option<Point> {
+        let d1 = Point::new(self.x2 - self.x1, self.y2 - self.y1);
+        let d2 = Point::new(other.x2 - other.x1, other.y2 - other.y1);
+        let denom = d1.x * d2.y - d1.y * d2.x;
+        if denom.abs() < Self::DEFAULT_EPSILON {
             return None; // parallel
         }
 
-        let x = ((x1 * y2 - y1 * x2) * (x3 - x4) - (x1 - x2) * (x3 * y4 - y3 * x4)) / x_denom;
-        let y = ((x1 * y2 - y1 * x2) * (y3 - y4) - (y1 - y2) * (x3 * y4 - y3 * x4)) / y_denom;
+        let to_other = Point::new(other.x1 - self.x1, other.y1 - self.y1);
+        let t = (to_other.x * d2.y - to_other.y * d2.x) / denom;
+
+        Some(Point::new(self.x1 + t * d1.x, self.y1 + t * d1.y))
+    }
+}
+
+#[test]
+fn test_line_intersection_bounded_to_segments() {
+    // an X crossing through both segments' midpoints -- the ordinary case
+    let a = Line::new(0.0, 0.0, 2.0, 2.0);
+    let b = Line::new(0.0, 2.0, 2.0, 0.0);
+    let point = a.intersection(b).unwrap();
+    assert!((point.x - 1.0).abs() < 1e-6);
+    assert!((point.y - 1.0).abs() < 1e-6);
+
+    // same infinite lines, but shifted so they only cross well past where
+    // either segment actually ends -- intersection_unbounded would find a
+    // point here, the bounded intersection must not
+    let a = Line::new(0.0, 0.0, 1.0, 0.0);
+    let b = Line::new(5.0, -1.0, 5.0, 1.0);
+    assert_eq!(a.intersection(b), None);
+    assert!(a.intersection_unbounded(b).is_some());
+
+    // parallel, non-collinear segments never meet
+    let a = Line::new(0.0, 0.0, 1.0, 0.0);
+    let b = Line::new(0.0, 1.0, 1.0, 1.0);
+    assert_eq!(a.intersection(b), None);
+
+    // collinear and overlapping -- reports the midpoint of the overlap
+    let a = Line::new(0.0, 0.0, 4.0, 0.0);
+    let b = Line::new(2.0, 0.0, 6.0, 0.0);
+    let point = a.intersection(b).unwrap();
+    assert!((point.x - 3.0).abs() < 1e-6);
+    assert!((point.y - 0.0).abs() < 1e-6);
+
+    // collinear but not overlapping at all
+    let a = Line::new(0.0, 0.0, 1.0, 0.0);
+    let b = Line::new(2.0, 0.0, 3.0, 0.0);
+    assert_eq!(a.intersection(b), None);
+}
+
+// quadratic Bezier B(s) = (1-s)^2 P0 + 2(1-s)s C + s^2 P1 -- rounds off a
+// car's path at a turn (P0/P1 the straight points either side of the
+// corner, C the corner itself) instead of snapping at a right angle, see
+// CarRenderer::render_bounds.
+pub struct CurvePath {
+    p0: Point,
+    c: Point,
+    p1: Point,
+}
+
+impl CurvePath {
+    pub fn new(p0: Point, c: Point, p1: Point) -> Self {
+        Self { p0, c, p1 }
+    }
+
+    pub fn point_at(&self, s: f32) -> Point {
+        self.p0 * (1.0 - s) * (1.0 - s) + self.c * (2.0 * (1.0 - s) * s) + self.p1 * (s * s)
+    }
+
+    // derivative of point_at, normalized to a heading -- used to rotate a
+    // car's sprite to face its direction of travel through the turn.
+    pub fn tangent_at(&self, s: f32) -> Point {
+        let tangent = (self.c - self.p0) * (2.0 * (1.0 - s)) + (self.p1 - self.c) * (2.0 * s);
+        tangent.normalized()
+    }
+
+    // recursively de Casteljau-splits this curve at s=0.5 until the control
+    // point's distance from the chord P0->P1 is within `tolerance`, then
+    // returns the flattened points as a polyline from P0 to P1.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Point> {
+        let mut points = vec![self.p0];
+        self.flatten_into(tolerance, &mut points);
+        points
+    }
+
+    fn flatten_into(&self, tolerance: f32, points: &mut Vec<Point>) {
+        if self.control_distance_from_chord() <= tolerance {
+            points.push(self.p1);
+            return;
+        }
+
+        let (left, right) = self.split();
+        left.flatten_into(tolerance, points);
+        right.flatten_into(tolerance, points);
+    }
+
+    // perpendicular distance from C to the chord P0->P1 (how far the curve
+    // can possibly bulge away from a straight line at this subdivision)
+    fn control_distance_from_chord(&self) -> f32 {
+        let chord = self.p1 - self.p0;
+        let chord_length = chord.length();
+        if chord_length == 0.0 {
+            return (self.c - self.p0).length();
+        }
+
+        let to_control = self.c - self.p0;
+        (chord.x * to_control.y - chord.y * to_control.x).abs() / chord_length
+    }
+
+    // de Casteljau split at s=0.5: two quadratic Beziers covering s in
+    // [0, 0.5] and [0.5, 1] of the original curve.
+    fn split(&self) -> (Self, Self) {
+        let p01 = self.p0.lerp(self.c, 0.5);
+        let p12 = self.c.lerp(self.p1, 0.5);
+        let mid = p01.lerp(p12, 0.5);
+
+        (Self::new(self.p0, p01, mid), Self::new(mid, p12, self.p1))
+    }
+}
 
-        Some((x, y))
+#[test]
+fn test_curve_path_flatten() {
+    // a straight "curve" (control point on the chord) is already flat --
+    // one split shouldn't be needed regardless of tolerance
+    let straight = CurvePath::new(
+        Point::new(0.0, 0.0),
+        Point::new(1.0, 0.0),
+        Point::new(2.0, 0.0),
+    );
+    assert_eq!(
+        straight.flatten(0.01),
+        vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0)]
+    );
+
+    // a real corner: flattening always starts at p0 and ends at p1, and a
+    // tighter tolerance should never produce fewer points than a looser one
+    // (more subdivision, not less)
+    let corner = CurvePath::new(
+        Point::new(0.0, 0.0),
+        Point::new(2.0, 0.0),
+        Point::new(2.0, 2.0),
+    );
+    let loose = corner.flatten(1.0);
+    let tight = corner.flatten(0.01);
+
+    assert_eq!(*loose.first().unwrap(), corner.p0);
+    assert_eq!(*loose.last().unwrap(), corner.p1);
+    assert_eq!(*tight.first().unwrap(), corner.p0);
+    assert_eq!(*tight.last().unwrap(), corner.p1);
+    assert!(tight.len() >= loose.len());
+
+    // every flattened segment should lie within `tolerance` of the curve it
+    // approximates -- loosely checked by measuring each segment's midpoint
+    // against a dense sampling of the curve itself
+    let tolerance = 0.01;
+    for window in tight.windows(2) {
+        let segment_mid = window[0].lerp(window[1], 0.5);
+        let distance_to_curve = (0..=1000)
+            .map(|i| (corner.point_at(i as f32 / 1000.0) - segment_mid).length())
+            .fold(f32::INFINITY, f32::min);
+        assert!(distance_to_curve <= tolerance);
     }
 }
 
 pub struct RoadCoords {
     position: CarPosition,
-    x: f32,
-    y: f32,
-    sidewalk_direction: Direction,
+    center: Point,
+    sidewalk_angle: Angle,
 }
 
 impl RoadCoords {
     pub fn new(position: CarPosition, grid: &GridRenderer) -> Self {
         // get the rectangle of the car
         let road = grid.road_at(position.road_section);
-        let car_rect = CarRenderer::rect_from_position(position, &road);
+        // not about any particular car's model here, just reusing the single-
+        // cell box as a generic landmark (sidewalk offset, passenger icon, ...)
+        let car_rect = CarRenderer::rect_from_position(position, &road, 1);
 
         // get the center of the rectangle
-        let x = car_rect.left() + car_rect.w / 2.0;
-        let y = car_rect.top() + car_rect.h / 2.0;
-
-        // get the direction towards the sidewalk
-        let road_direction = position.road_section.direction;
-        let sidewalk_direction = match CarRenderer::ENGLAND_MODE {
-            true => road_direction.counterclockwise(),
-            false => road_direction.clockwise(),
+        let center = Point::new(
+            car_rect.left() + car_rect.w / 2.0,
+            car_rect.top() + car_rect.h / 2.0,
+        );
+
+        // the sidewalk sits ±90° off the road's own heading, rotated one
+        // way or the other depending which side traffic drives on -- this
+        // works for any road heading, not just the 4 cardinal Directions
+        // (the old clockwise/counterclockwise cardinal switch this replaces
+        // only ever made sense for those 4)
+        let road_angle = Self::direction_angle(position.road_section.direction);
+        let rotation = match grid.grid.opts.driving_side.is_left() {
+            true => -std::f32::consts::FRAC_PI_2,
+            false => std::f32::consts::FRAC_PI_2,
         };
+        let sidewalk_angle = Angle(road_angle.0 + rotation);
 
         Self {
             position,
-            x,
-            y,
-            sidewalk_direction,
+            center,
+            sidewalk_angle,
         }
     }
 
-    pub fn offset_coords(&self, offset: f32) -> (f32, f32) {
-        match self.sidewalk_direction {
-            Direction::Up => (self.x, self.y - offset),
-            Direction::Down => (self.x, self.y + offset),
-            Direction::Left => (self.x - offset, self.y),
-            Direction::Right => (self.x + offset, self.y),
+    // cardinal Direction as an Angle -- screen space has +y down, so
+    // Up/Down are inverted relative to compass intuition, same as
+    // Direction::offset.
+    fn direction_angle(direction: Direction) -> Angle {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match direction {
+            Direction::Right => Angle(0.0),
+            Direction::Down => Angle(FRAC_PI_2),
+            Direction::Left => Angle(PI),
+            Direction::Up => Angle(-FRAC_PI_2),
         }
     }
 
-    pub fn sidewalk_coords(&self, offset: f32) -> (f32, f32) {
+    pub fn offset_coords(&self, offset: f32) -> Point {
+        self.center + self.sidewalk_angle.unit_vec() * offset
+    }
+
+    pub fn sidewalk_coords(&self, offset: f32) -> Point {
         self.offset_coords(RoadRenderer::WIDTH / 2.0 + offset)
     }
 }